@@ -0,0 +1,353 @@
+//! Syntax highlighting of Rust, as it is typed out.
+//!
+//! The source comes in byte by byte, in chunks split anywhere, so the highlighter is a state
+//! machine remembering where it is between bytes. Bytes whose color isn't known yet, like the
+//! start of a word which may turn out to be a keyword, are held back until it is.
+//!
+//! This is not a full lexer: raw strings are taken as plain strings, and anything unknown is
+//! plain code.
+
+use termion::{color, style};
+
+/// The keywords of Rust.
+const KEYWORDS: &'static [&'static str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+    "type", "unsafe", "use", "where", "while",
+];
+
+/// The kind of a piece of code, giving its color.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Class {
+    /// Plain code.
+    Plain,
+    /// A keyword.
+    Keyword,
+    /// A string or character literal.
+    Str,
+    /// A comment.
+    Comment,
+    /// An attribute.
+    Attribute,
+    /// A number.
+    Number,
+}
+
+impl Class {
+    /// Get the escape sequence switching to the color of the class.
+    fn color(self) -> String {
+        match self {
+            Class::Plain => format!("{}{}", style::Reset, color::Fg(color::Green)),
+            Class::Keyword => format!("{}{}", style::Bold, color::Fg(color::LightGreen)),
+            Class::Str => format!("{}{}", style::Reset, color::Fg(color::Yellow)),
+            Class::Comment => format!("{}{}", style::Reset, color::Fg(color::LightBlack)),
+            Class::Attribute => format!("{}{}", style::Reset, color::Fg(color::Magenta)),
+            Class::Number => format!("{}{}", style::Reset, color::Fg(color::Cyan)),
+        }
+    }
+}
+
+/// Where the highlighter is in the source.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum State {
+    /// Between tokens.
+    Code,
+    /// In a word, held back until it ends.
+    Word,
+    /// In a number.
+    Number,
+    /// After a `/`, held back until we know if it starts a comment.
+    Slash,
+    /// In a line comment.
+    LineComment,
+    /// In a block comment, nested `depth` times, the last byte being `last`.
+    BlockComment {
+        /// The number of comments open.
+        depth: usize,
+        /// The last byte of the comment.
+        last: u8,
+    },
+    /// In a string or character literal ending with `end`.
+    Str {
+        /// The quote ending the literal.
+        end: u8,
+        /// Does the next byte follow a backslash?
+        escaped: bool,
+    },
+    /// After a `'`, held back with the next byte until we know if it is a character literal or a
+    /// lifetime.
+    Quote,
+    /// After a `#` or `#!`, held back until we know if it starts an attribute.
+    Hash,
+    /// In an attribute, `depth` brackets deep.
+    Attribute {
+        /// The number of brackets open.
+        depth: usize,
+    },
+}
+
+/// A highlighter of Rust.
+pub struct Highlighter {
+    /// Where we are in the source.
+    state: State,
+    /// The bytes held back.
+    pending: Vec<u8>,
+    /// The class of the last byte written, if any.
+    class: Option<Class>,
+}
+
+/// Is the byte part of words?
+fn is_word(b: u8) -> bool {
+    b == b'_' || b.is_ascii_alphanumeric()
+}
+
+impl Highlighter {
+    /// Create a highlighter, starting between tokens.
+    pub fn new() -> Highlighter {
+        Highlighter {
+            state: State::Code,
+            pending: Vec::new(),
+            class: None,
+        }
+    }
+
+    /// Write bytes of the given class, switching colors if needed.
+    ///
+    /// Newlines are written as `\n\r`, as the terminal is in raw mode.
+    fn emit(&mut self, class: Class, bytes: &[u8], out: &mut Vec<u8>) {
+        // Whitespace looks the same in every color.
+        if self.class != Some(class) && !bytes.iter().all(|b| b.is_ascii_whitespace()) {
+            out.extend_from_slice(class.color().as_bytes());
+            self.class = Some(class);
+        }
+
+        for &b in bytes {
+            if b == b'\n' {
+                out.extend_from_slice(b"\n\r");
+            } else {
+                out.push(b);
+            }
+        }
+    }
+
+    /// Feed the next byte of the source, writing what can be written to `out`.
+    pub fn feed(&mut self, b: u8, out: &mut Vec<u8>) {
+        match self.state {
+            State::Code => self.start(b, out),
+            State::Word if is_word(b) => self.pending.push(b),
+            State::Word => {
+                // The word is over, and we know what it is.
+                let word = ::std::mem::replace(&mut self.pending, Vec::new());
+                let keyword = KEYWORDS.iter().any(|k| k.as_bytes() == &word[..]);
+                self.emit(if keyword { Class::Keyword } else { Class::Plain }, &word, out);
+                self.start(b, out);
+            },
+            State::Number if is_word(b) => self.emit(Class::Number, &[b], out),
+            State::Number => self.start(b, out),
+            State::Slash => {
+                self.pending.clear();
+                match b {
+                    b'/' => {
+                        self.state = State::LineComment;
+                        self.emit(Class::Comment, b"//", out);
+                    },
+                    b'*' => {
+                        self.state = State::BlockComment { depth: 1, last: 0 };
+                        self.emit(Class::Comment, b"/*", out);
+                    },
+                    _ => {
+                        self.emit(Class::Plain, b"/", out);
+                        self.start(b, out);
+                    },
+                }
+            },
+            State::LineComment => {
+                if b == b'\n' {
+                    self.state = State::Code;
+                }
+                self.emit(Class::Comment, &[b], out);
+            },
+            State::BlockComment { depth, last } => {
+                self.emit(Class::Comment, &[b], out);
+                // A byte ending or starting a comment isn't the start of another.
+                self.state = match (last, b) {
+                    (b'*', b'/') if depth == 1 => State::Code,
+                    (b'*', b'/') => State::BlockComment { depth: depth - 1, last: 0 },
+                    (b'/', b'*') => State::BlockComment { depth: depth + 1, last: 0 },
+                    _ => State::BlockComment { depth: depth, last: b },
+                };
+            },
+            State::Str { end, escaped } => {
+                self.emit(Class::Str, &[b], out);
+                self.state = if escaped {
+                    State::Str { end: end, escaped: false }
+                } else if b == b'\\' {
+                    State::Str { end: end, escaped: true }
+                } else if b == end {
+                    State::Code
+                } else {
+                    State::Str { end: end, escaped: false }
+                };
+            },
+            State::Quote => {
+                if self.pending.len() == 1 {
+                    if b == b'\\' {
+                        // An escaped character, like '\n'.
+                        self.pending.clear();
+                        self.state = State::Str { end: b'\'', escaped: true };
+                        self.emit(Class::Str, b"'\\", out);
+                    } else {
+                        self.pending.push(b);
+                    }
+                } else if b == b'\'' {
+                    // A character, like 'a'.
+                    let mut literal = ::std::mem::replace(&mut self.pending, Vec::new());
+                    literal.push(b);
+                    self.state = State::Code;
+                    self.emit(Class::Str, &literal, out);
+                } else {
+                    // A lifetime, like 'a, which is plain code.
+                    let next = self.pending[1];
+                    self.pending.clear();
+                    self.state = State::Code;
+                    self.emit(Class::Plain, b"'", out);
+                    self.feed(next, out);
+                    self.feed(b, out);
+                }
+            },
+            State::Hash => {
+                match b {
+                    b'!' if self.pending == b"#" => self.pending.push(b),
+                    b'[' => {
+                        let mut start = ::std::mem::replace(&mut self.pending, Vec::new());
+                        start.push(b);
+                        self.state = State::Attribute { depth: 1 };
+                        self.emit(Class::Attribute, &start, out);
+                    },
+                    _ => {
+                        let hash = ::std::mem::replace(&mut self.pending, Vec::new());
+                        self.state = State::Code;
+                        self.emit(Class::Plain, &hash, out);
+                        self.feed(b, out);
+                    },
+                }
+            },
+            State::Attribute { depth } => {
+                self.emit(Class::Attribute, &[b], out);
+                self.state = match b {
+                    b'[' => State::Attribute { depth: depth + 1 },
+                    b']' if depth == 1 => State::Code,
+                    b']' => State::Attribute { depth: depth - 1 },
+                    _ => State::Attribute { depth: depth },
+                };
+            },
+        }
+    }
+
+    /// Start a new token with the given byte.
+    fn start(&mut self, b: u8, out: &mut Vec<u8>) {
+        self.state = State::Code;
+        match b {
+            b if b.is_ascii_digit() => {
+                self.state = State::Number;
+                self.emit(Class::Number, &[b], out);
+            },
+            b if is_word(b) => {
+                self.state = State::Word;
+                self.pending.push(b);
+            },
+            b'"' => {
+                self.state = State::Str { end: b'"', escaped: false };
+                self.emit(Class::Str, &[b], out);
+            },
+            b'/' => {
+                self.state = State::Slash;
+                self.pending.push(b);
+            },
+            b'\'' => {
+                self.state = State::Quote;
+                self.pending.push(b);
+            },
+            b'#' => {
+                self.state = State::Hash;
+                self.pending.push(b);
+            },
+            _ => self.emit(Class::Plain, &[b], out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Highlight a source fed in the given chunks, as it is typed out.
+    fn highlight(chunks: &[&[u8]]) -> Vec<u8> {
+        let mut highlighter = Highlighter::new();
+        let mut out = Vec::new();
+        for chunk in chunks {
+            for &b in chunk.iter() {
+                highlighter.feed(b, &mut out);
+            }
+        }
+        out
+    }
+
+    /// Check that a source is highlighted as the given pieces, however it is split into chunks.
+    ///
+    /// The source should end with a newline, so nothing is held back at the end.
+    fn check(src: &str, pieces: &[(Class, &str)]) {
+        let src = src.as_bytes();
+        let mut expected = Vec::new();
+        for &(class, text) in pieces {
+            expected.extend_from_slice(class.color().as_bytes());
+            expected.extend_from_slice(text.as_bytes());
+        }
+
+        assert_eq!(highlight(&[src]), expected);
+        for i in 1..src.len() {
+            assert_eq!(highlight(&[&src[..i], &src[i..]]), expected, "split at {}", i);
+        }
+        for len in 1..4 {
+            let chunks: Vec<&[u8]> = src.chunks(len).collect();
+            assert_eq!(highlight(&chunks), expected, "chunks of {}", len);
+        }
+    }
+
+    #[test]
+    fn keywords() {
+        check("(fn) (fnord)\n",
+              &[(Class::Plain, "("), (Class::Keyword, "fn"), (Class::Plain, ") (fnord)\n\r")]);
+    }
+
+    #[test]
+    fn characters_and_lifetimes() {
+        check("f('a', &'a x)\n",
+              &[(Class::Plain, "f("), (Class::Str, "'a'"), (Class::Plain, ", &'a x)\n\r")]);
+    }
+
+    #[test]
+    fn nested_block_comments() {
+        check("x /* a /* b */ c */ y\n",
+              &[(Class::Plain, "x "), (Class::Comment, "/* a /* b */ c */ "),
+                (Class::Plain, "y\n\r")]);
+    }
+
+    #[test]
+    fn line_comments() {
+        check("a // b */ c\nd\n",
+              &[(Class::Plain, "a "), (Class::Comment, "// b */ c\n\r"), (Class::Plain, "d\n\r")]);
+    }
+
+    #[test]
+    fn attributes() {
+        check("#[derive(Debug)] #x\n",
+              &[(Class::Attribute, "#[derive(Debug)] "), (Class::Plain, "#x\n\r")]);
+    }
+
+    #[test]
+    fn string_escapes() {
+        check("\"\\\"\" x\n", &[(Class::Str, "\"\\\"\" "), (Class::Plain, "x\n\r")]);
+    }
+}
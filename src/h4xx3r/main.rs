@@ -1,9 +1,35 @@
 extern crate termion;
 
-use termion::{clear, color, cursor, style};
+mod highlight;
+
+use termion::{clear, cursor, style};
 use termion::raw::IntoRawMode;
+use std::env;
+use std::fs::{self, File};
 use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use highlight::Highlighter;
+
+/// The help page.
+const HELP: &'static str = r#"
+h4xx3r ~ type like a l33t h4xx3r.
+
+usage:
+    h4xx3r [PATH...] ~ type out the given source files, and the files of the given
+                       directories, or a built-in kernel snippet.
+
+flags:
+    --ext EXT ~ only take the files with the extension EXT (like rs, without the dot)
+                from directories. May be given several times. Defaults to rs.
 
+controls:
+    any key ~ type some more, highlighted as Rust.
+    C-D     ~ exit.
+"#;
+
+/// The number of bytes typed per key.
 const SPEED: usize = 7;
 
 const GUI_INTERFACE_IN_VISUAL_BASIC: &'static [u8] = br#"
@@ -531,7 +557,115 @@ pub extern "cdecl" fn kernel(interrupt: usize, mut regs: &mut Regs) {
 // Oh god, I'm so good that I MUST contribute to the Redox operating system...
 "#;
 
+/// Add the files of a directory ending with one of the given extensions to `files`, walking
+/// through its subdirectories, in order of name.
+///
+/// Hidden files and directories are skipped, and so are links to directories.
+fn walk(dir: &Path, exts: &[String], files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        // Unlike the metadata of the path, the file type doesn't follow links.
+        entries.push((entry.path(), entry.file_type()?));
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (path, file_type) in entries {
+        if path.file_name().map_or(false, |name| name.to_string_lossy().starts_with('.')) {
+            continue;
+        }
+
+        if file_type.is_dir() {
+            walk(&path, exts, files)?;
+        } else if file_type.is_symlink() && path.is_dir() {
+            // A link to a directory may lead back up the tree, walking it round in circles.
+            continue;
+        } else if path.extension().map_or(false, |ext| exts.iter().any(|e| ext == e.as_str())) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the source to type out from the given files and directories, one after another.
+fn read_corpus(paths: &[PathBuf], exts: &[String]) -> io::Result<Vec<u8>> {
+    let mut corpus = Vec::new();
+    for path in paths {
+        let mut files = Vec::new();
+        if path.is_dir() {
+            walk(path, exts, &mut files)?;
+        } else {
+            files.push(path.clone());
+        }
+
+        for file in files {
+            let mut text = Vec::new();
+            File::open(&file)
+                .and_then(|mut f| f.read_to_end(&mut text))
+                .map_err(|err| io::Error::new(err.kind(),
+                                              format!("{}: {}", file.display(), err)))?;
+            corpus.extend(text.into_iter().filter(|&c| c != b'\r'));
+            // Keep the files apart.
+            corpus.extend_from_slice(b"\n\n");
+        }
+    }
+
+    Ok(corpus)
+}
+
 fn main() {
+    let mut args = env::args().skip(1);
+    let mut paths = Vec::new();
+    let mut exts = Vec::new();
+
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                // Print the help page.
+                let stdout = io::stdout();
+                let mut stdout = stdout.lock();
+                stdout.write(HELP.as_bytes()).unwrap();
+                stdout.flush().unwrap();
+                process::exit(0);
+            },
+            "--ext" => exts.push(args.next().unwrap_or_else(|| {
+                stderr.write(b"no extension given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            })),
+            arg if arg.starts_with('-') => {
+                stderr.write(b"Unknown argument.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            },
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+
+    if exts.is_empty() {
+        exts.push("rs".to_string());
+    }
+
+    // Read the source before touching the terminal, so errors can be reported properly.
+    let corpus = if paths.is_empty() {
+        GUI_INTERFACE_IN_VISUAL_BASIC.to_vec()
+    } else {
+        read_corpus(&paths, &exts).unwrap_or_else(|err| {
+            writeln!(stderr, "{}", err).unwrap();
+            stderr.flush().unwrap();
+            process::exit(1);
+        })
+    };
+    if corpus.iter().all(|c| c.is_ascii_whitespace()) {
+        stderr.write(b"no source found.\n").unwrap();
+        stderr.flush().unwrap();
+        process::exit(1);
+    }
+
     let stdin = io::stdin();
     let stdout = io::stdout();
     let mut stdout = stdout.lock().into_raw_mode().unwrap();
@@ -542,18 +676,15 @@ fn main() {
 
     stdout.flush().unwrap();
 
-    write!(stdout, "{}", color::Fg(color::Green)).unwrap();
+    let mut highlighter = Highlighter::new();
 
     for b in stdin.lock().bytes() {
-        let out = &GUI_INTERFACE_IN_VISUAL_BASIC[n % GUI_INTERFACE_IN_VISUAL_BASIC.len()..(n + SPEED) % GUI_INTERFACE_IN_VISUAL_BASIC.len()];
-
-        for &b in out {
-            if b == b'\n' {
-                stdout.write(b"\n\r").unwrap();
-            } else {
-                stdout.write(&[b]).unwrap();
-            }
+        // Start over when the end is reached.
+        let mut out = Vec::new();
+        for i in n..n + SPEED {
+            highlighter.feed(corpus[i % corpus.len()], &mut out);
         }
+        stdout.write_all(&out).unwrap();
         stdout.flush().unwrap();
 
         n += SPEED;
@@ -0,0 +1,284 @@
+//! The level editor.
+//!
+//! The map is painted cell by cell with the cursor, and can be resized, saved in the level format
+//! and played right away.
+
+use termion::{clear, cursor, style, terminal_size};
+use termion::raw::{IntoRawMode, RawTerminal};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+use level::Level;
+use Game;
+
+/// The width of new maps, walls included.
+const NEW_WIDTH: usize = 20;
+/// The height of new maps, walls included.
+const NEW_HEIGHT: usize = 10;
+
+/// The tiles which can be painted, each with the key painting it.
+const TILES: &'static [u8] = b"# &@.O$+=<>^v";
+
+/// The message asking to confirm quitting without saving.
+const QUIT_MESSAGE: &'static str = "unsaved changes, press q again to quit";
+
+/// The state of the editor.
+pub struct Editor<R, W: Write> {
+    /// Standard output.
+    stdout: W,
+    /// Standard input.
+    stdin: R,
+    /// The file edited.
+    path: PathBuf,
+    /// The rows of the map, all equally wide.
+    rows: Vec<Vec<u8>>,
+    /// The par line following the map, if any, kept as is until the map is changed.
+    par: Option<Vec<u8>>,
+    /// The x coordinate of the cursor.
+    x: usize,
+    /// The y coordinate of the cursor.
+    y: usize,
+    /// The message shown below the help, if any.
+    message: String,
+    /// Was the map changed since it was last saved?
+    modified: bool,
+    /// Test play in plain ASCII, without colors?
+    ascii: bool,
+}
+
+impl<R: Read, W: Write> Editor<R, W> {
+    /// Open a level file in the editor.
+    ///
+    /// If the file doesn't exist, a new walled map is created, to be saved there.
+    pub fn new(stdin: R, stdout: W, path: PathBuf, ascii: bool)
+               -> io::Result<Editor<R, RawTerminal<W>>> {
+        let mut text = Vec::new();
+        match File::open(&path) {
+            Ok(mut file) => {
+                file.read_to_end(&mut text)?;
+            },
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {},
+            Err(err) => return Err(err),
+        }
+
+        text.retain(|&c| c != b'\r');
+        let mut rows: Vec<Vec<u8>> = text.split(|&c| c == b'\n').map(|row| row.to_vec()).collect();
+        // The last row ends with a newline, leaving an empty row behind.
+        if rows.last().map_or(false, |row| row.is_empty()) {
+            rows.pop();
+        }
+        let par = if rows.last().map_or(false, |row| row.starts_with(b"par ")) {
+            rows.pop()
+        } else {
+            None
+        };
+
+        let message;
+        if rows.is_empty() {
+            rows = new_map();
+            message = "new level".to_string();
+        } else {
+            // Pad the rows to the widest one, so the map can be edited even if it is malformed.
+            let width = rows.iter().map(|row| row.len()).max().unwrap_or(0).max(1);
+            for row in rows.iter_mut() {
+                row.resize(width, b' ');
+            }
+            message = String::new();
+        }
+
+        Ok(Editor {
+            stdout: stdout.into_raw_mode()?,
+            stdin: stdin,
+            path: path,
+            rows: rows,
+            par: par,
+            x: 0,
+            y: 0,
+            message: message,
+            modified: false,
+            ascii: ascii,
+        })
+    }
+
+    /// Start the editor loop.
+    pub fn start(&mut self) {
+        loop {
+            self.draw();
+
+            // Read a single byte from stdin.
+            let mut b = [0];
+            self.stdin.read(&mut b).unwrap();
+
+            // Quitting with unsaved changes needs a second `q`.
+            let confirmed = self.message == QUIT_MESSAGE;
+            self.message.clear();
+            match b[0] {
+                b'h' => self.x = self.x.saturating_sub(1),
+                b'j' => self.y = (self.y + 1).min(self.rows.len() - 1),
+                b'k' => self.y = self.y.saturating_sub(1),
+                b'l' => self.x = (self.x + 1).min(self.width() - 1),
+                b'H' => self.resize(-1, 0),
+                b'J' => self.resize(0, 1),
+                b'K' => self.resize(0, -1),
+                b'L' => self.resize(1, 0),
+                b'p' => self.play(),
+                b's' => self.save(),
+                b'q' if self.modified && !confirmed => self.message = QUIT_MESSAGE.to_string(),
+                b'q' => return,
+                c if TILES.contains(&c) => self.paint(c),
+                _ => {},
+            }
+        }
+    }
+
+    /// Get the width of the map.
+    fn width(&self) -> usize {
+        self.rows[0].len()
+    }
+
+    /// Get the map as text, in the level format.
+    fn text(&self) -> Vec<u8> {
+        let mut text = Vec::new();
+        for row in self.rows.iter() {
+            text.extend_from_slice(row);
+            text.push(b'\n');
+        }
+        if let Some(ref par) = self.par {
+            text.extend_from_slice(par);
+            text.push(b'\n');
+        }
+
+        text
+    }
+
+    /// Draw the map, and the help beside it.
+    fn draw(&mut self) {
+        write!(self.stdout, "{}{}", clear::All, cursor::Goto(1, 1)).unwrap();
+        for row in self.rows.iter() {
+            self.stdout.write_all(row).unwrap();
+            self.stdout.write_all(b"\n\r").unwrap();
+        }
+
+        let column = self.width() as u16 + 3;
+        let lines = [
+            format!("editing {}", self.path.display()),
+            format!("{}x{}, cursor at ({}, {})", self.width(), self.rows.len(), self.x, self.y),
+            String::new(),
+            "h j k l   move".to_string(),
+            "H J K L   resize".to_string(),
+            "# space   wall, ice".to_string(),
+            "& @       start, goal".to_string(),
+            ". O       rough floor, hole".to_string(),
+            "$         boulder".to_string(),
+            "+ =       key, door".to_string(),
+            "< > ^ v   arrows".to_string(),
+            "p         test play".to_string(),
+            "s         save".to_string(),
+            "q         quit".to_string(),
+            String::new(),
+        ];
+        for (i, line) in lines.iter().chain(Some(&self.message)).enumerate() {
+            write!(self.stdout, "{}{}", cursor::Goto(column, i as u16 + 2), line).unwrap();
+        }
+
+        write!(self.stdout, "{}", cursor::Goto(self.x as u16 + 1, self.y as u16 + 1)).unwrap();
+        self.stdout.flush().unwrap();
+    }
+
+    /// Paint the cell under the cursor.
+    fn paint(&mut self, tile: u8) {
+        // There is a single start, so the old one is replaced by ice.
+        if tile == b'&' {
+            for cell in self.rows.iter_mut().flat_map(|row| row.iter_mut()) {
+                if *cell == b'&' {
+                    *cell = b' ';
+                }
+            }
+        }
+
+        self.rows[self.y][self.x] = tile;
+        self.modify();
+    }
+
+    /// Note that the map changed.
+    ///
+    /// The par of the old map may be wrong for the new one, so it is dropped, and computed
+    /// again when the level is loaded.
+    fn modify(&mut self) {
+        if self.par.take().is_some() {
+            self.message = "par dropped, it is computed from the map instead".to_string();
+        }
+        self.modified = true;
+    }
+
+    /// Add or remove columns and rows at the right and bottom edges of the map.
+    ///
+    /// The map keeps at least one cell, and fits in the terminal. New cells are ice.
+    fn resize(&mut self, dx: isize, dy: isize) {
+        let (term_width, term_height) = terminal_size().unwrap_or((80, 24));
+        let width = (self.width() as isize + dx).max(1).min(term_width as isize) as usize;
+        let height = (self.rows.len() as isize + dy).max(1).min(term_height as isize) as usize;
+
+        self.rows.resize(height, Vec::new());
+        for row in self.rows.iter_mut() {
+            row.resize(width, b' ');
+        }
+        self.x = self.x.min(width - 1);
+        self.y = self.y.min(height - 1);
+        self.modify();
+    }
+
+    /// Save the map to the file edited.
+    ///
+    /// Invalid levels are saved too, so work in progress isn't lost, but the problem is shown.
+    fn save(&mut self) {
+        let text = self.text();
+        self.message = match File::create(&self.path).and_then(|mut file| file.write_all(&text)) {
+            Ok(()) => {
+                self.modified = false;
+                match Level::parse(&text) {
+                    Ok(_) => "saved".to_string(),
+                    Err(err) => format!("saved, but {}", err),
+                }
+            },
+            Err(err) => format!("failed to save: {}", err),
+        };
+    }
+
+    /// Play the map, returning to the editor when the player quits.
+    fn play(&mut self) {
+        let level = match Level::parse(&self.text()) {
+            Ok(level) => level,
+            Err(err) => {
+                self.message = err.to_string();
+                return;
+            },
+        };
+
+        Game::new(&mut self.stdin, &mut self.stdout, vec![level], None, None, self.ascii).start();
+    }
+}
+
+impl<R, W: Write> Drop for Editor<R, W> {
+    fn drop(&mut self) {
+        // When done, restore the defaults to avoid messing with the terminal.
+        write!(self.stdout, "{}{}{}", clear::All, style::Reset, cursor::Goto(1, 1)).unwrap();
+    }
+}
+
+/// Create a new map, walled, with the start and the goal in opposite corners.
+fn new_map() -> Vec<Vec<u8>> {
+    let mut rows = vec![vec![b' '; NEW_WIDTH]; NEW_HEIGHT];
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            if x == 0 || y == 0 || x + 1 == NEW_WIDTH || y + 1 == NEW_HEIGHT {
+                *cell = b'#';
+            }
+        }
+    }
+    rows[1][1] = b'&';
+    rows[NEW_HEIGHT - 2][NEW_WIDTH - 2] = b'@';
+
+    rows
+}
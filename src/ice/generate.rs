@@ -0,0 +1,104 @@
+//! A generator of random levels.
+//!
+//! Rocks are scattered over a walled field of ice, and the goal is put on a random ice cell. The
+//! solver then finds the start farthest from the goal among a few random candidates. Levels
+//! whose shortest solution is shorter than the requested difficulty are thrown away, and we try
+//! again.
+
+use extra::rand::Randomizer;
+
+use level::Level;
+use solver;
+
+/// The width of the generated maps, walls included.
+pub const WIDTH: u16 = 40;
+/// The height of the generated maps, walls included.
+pub const HEIGHT: u16 = 20;
+
+/// One cell in this many is a rock.
+const ROCKS: u8 = 7;
+/// The number of starts tried on every map.
+const STARTS: usize = 16;
+/// The number of maps tried before settling for the hardest one found.
+const ATTEMPTS: usize = 2000;
+
+/// A generator of random levels.
+pub struct Generator {
+    /// The randomizer.
+    rand: Randomizer,
+    /// The fewest moves the generated levels should take.
+    difficulty: u16,
+}
+
+impl Generator {
+    /// Create a generator of levels taking at least `difficulty` moves.
+    pub fn new(seed: u64, difficulty: u16) -> Generator {
+        Generator {
+            rand: Randomizer::new(seed),
+            difficulty: difficulty,
+        }
+    }
+
+    /// Get a random number below `n`.
+    fn rand_below(&mut self, n: usize) -> usize {
+        let mut res = 0;
+        for _ in 0..4 {
+            res = res << 8 | self.rand.read_u8() as usize;
+        }
+        res % n
+    }
+
+    /// Generate a level.
+    ///
+    /// If no level is hard enough after many attempts, the hardest one found is returned. The
+    /// level can always be solved.
+    pub fn generate(&mut self) -> Level {
+        let width = WIDTH as usize + 1;
+        let mut best: Option<(usize, Vec<u8>)> = None;
+
+        for _ in 0..ATTEMPTS {
+            let mut map = Vec::with_capacity(width * HEIGHT as usize);
+            for y in 0..HEIGHT {
+                for x in 0..WIDTH {
+                    let wall = x == 0 || y == 0 || x + 1 == WIDTH || y + 1 == HEIGHT;
+                    map.push(if wall || self.rand.read_u8() % ROCKS == 0 { b'#' } else { b' ' });
+                }
+                map.push(b'\n');
+            }
+
+            let ice: Vec<usize> = (0..map.len()).filter(|&i| map[i] == b' ').collect();
+            if ice.len() < 2 {
+                continue;
+            }
+            let goal = ice[self.rand_below(ice.len())];
+            map[goal] = b'@';
+
+            // Find the hardest start among a few.
+            for _ in 0..STARTS {
+                let start = ice[self.rand_below(ice.len())];
+                if start == goal {
+                    continue;
+                }
+
+                let (x, y) = ((start % width) as u16, (start / width) as u16);
+                let moves = match solver::solve(&map, width, x, y) {
+                    Ok(moves) => moves.len(),
+                    Err(_) => continue,
+                };
+
+                if best.as_ref().map_or(true, |&(best, _)| moves > best) {
+                    let mut level = map.clone();
+                    level[start] = b'&';
+                    best = Some((moves, level));
+                }
+            }
+
+            if best.as_ref().map_or(false, |&(moves, _)| moves >= self.difficulty as usize) {
+                break;
+            }
+        }
+
+        let (_, text) = best.expect("no solvable level generated");
+        Level::parse(&text).expect("invalid generated level")
+    }
+}
@@ -0,0 +1,537 @@
+//! The levels, and loading them from files.
+//!
+//! A level is a map in plain text, one line per row, all rows being equally wide. The player
+//! starts at `&`, or at (1, 1) if the map has no `&`, in which case that cell must be ice. The
+//! other tiles are:
+//!
+//! - ` `: ice, slid over.
+//! - `@`: the goal.
+//! - `.`: rough floor, stopping the player on it.
+//! - `O`: a hole, sending the player back to the start of the level.
+//! - `<`, `>`, `^` and `v`: one-way arrows, slid over in their direction only, and solid
+//!   otherwise.
+//! - `a` to `z`, except `v`: teleporters, coming in pairs. Sliding into one continues from the
+//!   other.
+//! - `$`: a boulder, on ice. The player stops against it, pushing it: the boulder then slides
+//!   over the ice until it hits anything else, or falls into a hole, filling it with ice.
+//! - `+`: a key, stopping the player on it. Picking it up opens every door, leaving ice behind.
+//! - `=`: a door, solid until a key is picked up.
+//! - Anything else is a solid block.
+//!
+//! The map may be followed by a line `par N`, giving the number of moves to aim for. Otherwise,
+//! the par is the fewest moves the level can be solved in.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use solver::{self, Unsolved};
+
+/// The levels shipped with the game.
+const BUILTIN: [&'static [u8]; 11] = [
+    include_bytes!("map1.txt"),
+    include_bytes!("map2.txt"),
+    include_bytes!("map3.txt"),
+    include_bytes!("map4.txt"),
+    include_bytes!("map5.txt"),
+    include_bytes!("map6.txt"),
+    include_bytes!("map7.txt"),
+    include_bytes!("map8.txt"),
+    include_bytes!("map9.txt"),
+    include_bytes!("map10.txt"),
+    include_bytes!("map11.txt"),
+];
+
+/// The character marking the start of the player.
+const START: u8 = b'&';
+/// The character marking the goal.
+pub const GOAL: u8 = b'@';
+/// The character of ice.
+pub const ICE: u8 = b' ';
+/// The character of rough floor.
+pub const FLOOR: u8 = b'.';
+/// The character of holes.
+pub const HOLE: u8 = b'O';
+/// The character of boulders.
+pub const BOULDER: u8 = b'$';
+/// The character of keys.
+pub const KEY: u8 = b'+';
+/// The character of doors.
+pub const DOOR: u8 = b'=';
+
+/// A direction.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Direction {
+    /// Up.
+    Up,
+    /// Down.
+    Down,
+    /// Left.
+    Left,
+    /// Right.
+    Right,
+}
+
+/// Every direction.
+pub const DIRECTIONS: [Direction; 4] =
+    [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+impl Direction {
+    /// Get the direction of a one-way arrow, if the character is one.
+    pub fn arrow(c: u8) -> Option<Direction> {
+        match c {
+            b'^' => Some(Direction::Up),
+            b'v' => Some(Direction::Down),
+            b'<' => Some(Direction::Left),
+            b'>' => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// Get the cell next to (x, y) in this direction.
+    ///
+    /// Moving out of the map ends up far off, where `get` sees a solid block.
+    pub fn next(self, x: u16, y: u16) -> (u16, u16) {
+        match self {
+            Direction::Right => (x.wrapping_add(1), y),
+            Direction::Left => (x.wrapping_sub(1), y),
+            Direction::Down => (x, y.wrapping_add(1)),
+            Direction::Up => (x, y.wrapping_sub(1)),
+        }
+    }
+
+    /// Get the name of the direction.
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
+    }
+}
+
+/// A single step of a slide.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Step {
+    /// The player slides on to the given cell.
+    Slide(u16, u16),
+    /// The player moves on to the given cell, and stops there.
+    Rest(u16, u16),
+    /// The player hits a solid block, and stops.
+    Stop,
+    /// The player reaches the goal.
+    Goal,
+    /// The player falls into a hole.
+    Fall,
+}
+
+/// How a slide ends.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum End {
+    /// The player stops.
+    Stop,
+    /// The player reaches the goal.
+    Goal,
+    /// The player falls into a hole.
+    Fall,
+}
+
+/// Is the character a teleporter?
+pub fn is_teleporter(c: u8) -> bool {
+    c >= b'a' && c <= b'z' && Direction::arrow(c).is_none()
+}
+
+/// Get the character of a given (x, y) of a map, `width` being the length of its rows, newline
+/// included.
+///
+/// Everything outside the map is solid.
+pub fn get(map: &[u8], width: usize, x: u16, y: u16) -> u8 {
+    let pos = y as usize * width + x as usize;
+    if x as usize >= width || pos >= map.len() {
+        b'#'
+    } else {
+        map[pos]
+    }
+}
+
+/// Calculate the next step of the player sliding from (x, y) in a given direction.
+fn step(map: &[u8], width: usize, x: u16, y: u16, dir: Direction) -> Step {
+    let (x, y) = dir.next(x, y);
+
+    match get(map, width, x, y) {
+        GOAL => Step::Goal,
+        ICE => Step::Slide(x, y),
+        FLOOR | KEY => Step::Rest(x, y),
+        HOLE => Step::Fall,
+        c if Direction::arrow(c) == Some(dir) => Step::Slide(x, y),
+        c if is_teleporter(c) => {
+            // Continue from the other teleporter of the pair.
+            let here = y as usize * width + x as usize;
+            match map.iter().enumerate().position(|(i, &t)| t == c && i != here) {
+                Some(there) => Step::Slide((there % width) as u16, (there / width) as u16),
+                None => Step::Stop,
+            }
+        },
+        _ => Step::Stop,
+    }
+}
+
+/// Slide from (x, y) in a given direction, until the player stops.
+///
+/// Returns the cells the player passes, in order, and how the slide ends.
+pub fn slide(map: &[u8], width: usize, mut x: u16, mut y: u16, dir: Direction)
+             -> (Vec<(u16, u16)>, End) {
+    let mut path = Vec::new();
+
+    loop {
+        // Teleporters may send the player round in circles. The player would then pass some
+        // cell twice before passing as many cells as the map has, so we stop there.
+        if path.len() > map.len() {
+            return (path, End::Stop);
+        }
+
+        match step(map, width, x, y, dir) {
+            Step::Slide(nx, ny) => {
+                x = nx;
+                y = ny;
+                path.push((x, y));
+            },
+            Step::Rest(nx, ny) => {
+                path.push((nx, ny));
+                return (path, End::Stop);
+            },
+            Step::Stop => return (path, End::Stop),
+            Step::Goal => return (path, End::Goal),
+            Step::Fall => return (path, End::Fall),
+        }
+    }
+}
+
+/// Push the boulder next to (x, y) in a given direction, if there is one, moving it on the map.
+///
+/// Returns the cells the boulder passes, in order. The boulder stays if it is blocked right away.
+pub fn push(map: &mut [u8], width: usize, x: u16, y: u16, dir: Direction) -> Vec<(u16, u16)> {
+    let mut path = Vec::new();
+    let (mut x, mut y) = dir.next(x, y);
+    if get(map, width, x, y) != BOULDER {
+        return path;
+    }
+
+    loop {
+        let (nx, ny) = dir.next(x, y);
+        let fill = match get(map, width, nx, ny) {
+            ICE => false,
+            HOLE => true,
+            _ => return path,
+        };
+
+        map[y as usize * width + x as usize] = ICE;
+        path.push((nx, ny));
+        if fill {
+            map[ny as usize * width + nx as usize] = ICE;
+            return path;
+        }
+        map[ny as usize * width + nx as usize] = BOULDER;
+        x = nx;
+        y = ny;
+    }
+}
+
+/// Pick up the key at (x, y), if there is one, opening every door of the map.
+///
+/// Returns whether a key was picked up.
+pub fn take_key(map: &mut [u8], width: usize, x: u16, y: u16) -> bool {
+    if get(map, width, x, y) != KEY {
+        return false;
+    }
+
+    map[y as usize * width + x as usize] = ICE;
+    for c in map.iter_mut().filter(|c| **c == DOOR) {
+        *c = ICE;
+    }
+
+    true
+}
+
+/// A level.
+pub struct Level {
+    /// The name of the level, shown on the level select.
+    pub name: String,
+    /// The map, one line per row, each ending with a newline.
+    ///
+    /// The start marker is replaced by ice. The boulders, keys and doors are where they start.
+    pub map: Vec<u8>,
+    /// The length of the rows, newline included.
+    pub width: usize,
+    /// The x coordinate of the start.
+    pub x: u16,
+    /// The y coordinate of the start.
+    pub y: u16,
+    /// The number of moves to aim for, or why it isn't known.
+    pub par: Result<u16, Unsolved>,
+}
+
+/// Create an error for a malformed level.
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid level: {}", msg))
+}
+
+impl Level {
+    /// Parse a level from its text.
+    pub fn parse(text: &[u8]) -> io::Result<Level> {
+        let text: Vec<u8> = text.iter().cloned().filter(|&c| c != b'\r').collect();
+        let mut rows: Vec<&[u8]> = text.split(|&c| c == b'\n').collect();
+        // The last row ends with a newline, leaving an empty row behind.
+        if rows.last().map_or(false, |row| row.is_empty()) {
+            rows.pop();
+        }
+
+        let mut par = None;
+        if rows.last().map_or(false, |row| row.starts_with(b"par ")) {
+            let line = String::from_utf8_lossy(&rows.pop().unwrap()[4..]).into_owned();
+            par = Some(line.trim().parse().map_err(|_| invalid("invalid par"))?);
+        }
+
+        let width = match rows.first() {
+            Some(row) if !row.is_empty() => row.len(),
+            _ => return Err(invalid("the map is empty")),
+        };
+        if width >= u16::max_value() as usize || rows.len() > u16::max_value() as usize {
+            return Err(invalid("the map is too large"));
+        }
+
+        let mut map = Vec::with_capacity((width + 1) * rows.len());
+        let mut start = None;
+        for (y, row) in rows.iter().enumerate() {
+            if row.len() != width {
+                return Err(invalid(&format!("row {} is {} wide, but row 1 is {} wide",
+                                            y + 1, row.len(), width)));
+            }
+
+            for (x, &c) in row.iter().enumerate() {
+                if c == START {
+                    if start.is_some() {
+                        return Err(invalid("more than one start (&)"));
+                    }
+                    start = Some((x as u16, y as u16));
+                    map.push(ICE);
+                } else {
+                    map.push(c);
+                }
+            }
+            map.push(b'\n');
+        }
+
+        let (x, y) = match start {
+            Some(start) => start,
+            // Without a marker, the player starts in the top-left corner, inside the walls.
+            None if rows.len() > 1 && width > 1 && rows[1][1] == ICE => (1, 1),
+            None => return Err(invalid("no start: mark it with & or leave (1, 1) as ice")),
+        };
+
+        if !map.contains(&GOAL) {
+            return Err(invalid("no goal (@)"));
+        }
+
+        for c in (b'a'..b'z' + 1).filter(|&c| is_teleporter(c)) {
+            match map.iter().filter(|&&t| t == c).count() {
+                0 | 2 => {},
+                1 => return Err(invalid(&format!("teleporter {} has no pair", c as char))),
+                _ => return Err(invalid(&format!("teleporter {} appears more than twice",
+                                                 c as char))),
+            }
+        }
+
+        let par = match par {
+            Some(par) => Ok(par),
+            None => solver::solve(&map, width + 1, x, y).map(|moves| moves.len() as u16),
+        };
+
+        Ok(Level {
+            name: String::new(),
+            map: map,
+            width: width + 1,
+            x: x,
+            y: y,
+            par: par,
+        })
+    }
+
+    /// Read a level from a file.
+    pub fn read(path: &Path) -> io::Result<Level> {
+        let mut text = Vec::new();
+        File::open(path)?.read_to_end(&mut text)?;
+
+        let mut level = Level::parse(&text)?;
+        level.name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+
+        Ok(level)
+    }
+
+    /// Get an identifier of the level, derived from its map and start.
+    ///
+    /// This is the 64-bit FNV-1a hash of both, in hexadecimal.
+    pub fn id(&self) -> String {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let start = [self.x as u8, (self.x >> 8) as u8, self.y as u8, (self.y >> 8) as u8];
+        for &byte in self.map.iter().chain(start.iter()) {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+
+        format!("{:016x}", hash)
+    }
+}
+
+/// Get the levels shipped with the game.
+pub fn builtin() -> Vec<Level> {
+    BUILTIN.iter().enumerate().map(|(i, text)| {
+        let mut level = Level::parse(text).expect("invalid built-in level");
+        level.name = format!("map{}", i + 1);
+        level
+    }).collect()
+}
+
+/// List the level files (ending with `.txt`) of a directory, sorted by name.
+pub fn list_dir(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut res = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().map_or(false, |ext| ext == "txt") {
+            res.push(path);
+        }
+    }
+    res.sort();
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver::{self, Unsolved};
+
+    /// Parse a level, which must be valid.
+    fn valid(text: &str) -> Level {
+        match Level::parse(text.as_bytes()) {
+            Ok(level) => level,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Get the error parsing an invalid level.
+    fn error(text: &str) -> String {
+        match Level::parse(text.as_bytes()) {
+            Ok(_) => panic!("the level is valid"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    /// Find the fewest moves solving a level.
+    fn solve(text: &str) -> Result<Vec<Direction>, Unsolved> {
+        let level = valid(text);
+        solver::solve(&level.map, level.width, level.x, level.y)
+    }
+
+    #[test]
+    fn parse() {
+        let level = valid("####\n#& #\n# @#\n####\npar 3\n");
+        assert_eq!(level.map, b"####\n#  #\n# @#\n####\n");
+        assert_eq!(level.width, 5);
+        assert_eq!((level.x, level.y), (1, 1));
+        assert_eq!(level.par, Ok(3));
+
+        // Without a marker, the player starts at (1, 1).
+        let level = valid("####\n#  #\n# @#\n####\n");
+        assert_eq!((level.x, level.y), (1, 1));
+        assert_eq!(level.par, Ok(2));
+    }
+
+    #[test]
+    fn invalid_par() {
+        assert_eq!(error("#&@#\npar x\n"), "invalid level: invalid par");
+    }
+
+    #[test]
+    fn empty_map() {
+        assert_eq!(error(""), "invalid level: the map is empty");
+        assert_eq!(error("par 3\n"), "invalid level: the map is empty");
+    }
+
+    #[test]
+    fn map_too_large() {
+        let row = "#".repeat(u16::max_value() as usize);
+        assert_eq!(error(&row), "invalid level: the map is too large");
+    }
+
+    #[test]
+    fn ragged_rows() {
+        assert_eq!(error("####\n#&@#\n###\n"),
+                   "invalid level: row 3 is 3 wide, but row 1 is 4 wide");
+    }
+
+    #[test]
+    fn more_than_one_start() {
+        assert_eq!(error("#&&@#\n"), "invalid level: more than one start (&)");
+    }
+
+    #[test]
+    fn no_start() {
+        assert_eq!(error("####\n##@#\n####\n"),
+                   "invalid level: no start: mark it with & or leave (1, 1) as ice");
+    }
+
+    #[test]
+    fn no_goal() {
+        assert_eq!(error("#& #\n"), "invalid level: no goal (@)");
+    }
+
+    #[test]
+    fn teleporter_without_pair() {
+        assert_eq!(error("#&a@#\n"), "invalid level: teleporter a has no pair");
+    }
+
+    #[test]
+    fn teleporter_more_than_twice() {
+        assert_eq!(error("#&bbb@#\n"), "invalid level: teleporter b appears more than twice");
+    }
+
+    #[test]
+    fn slide() {
+        let level = valid("#& . O@#\n");
+        let slide = |x| super::slide(&level.map, level.width, x, 0, Direction::Right);
+
+        // Rough floor stops the player on it, and a hole swallows the player.
+        assert_eq!(slide(1), (vec![(2, 0), (3, 0)], End::Stop));
+        assert_eq!(slide(3), (vec![(4, 0)], End::Fall));
+        assert_eq!(super::slide(&level.map, level.width, 1, 0, Direction::Left),
+                   (vec![], End::Stop));
+    }
+
+    #[test]
+    fn push_boulder_into_hole() {
+        let mut level = valid("#& $O @#\n");
+        assert_eq!(push(&mut level.map, level.width, 2, 0, Direction::Right), [(4, 0)]);
+        assert_eq!(level.map, b"#     @#\n".to_vec());
+
+        assert_eq!(solve("#& $O @#\n"), Ok(vec![Direction::Right, Direction::Right]));
+        assert_eq!(solve("#& $#O@#\n"), Err(Unsolved::Unreachable));
+    }
+
+    #[test]
+    fn key_opens_doors() {
+        let mut level = valid("#&+=@=#\n");
+        assert!(!take_key(&mut level.map, level.width, 1, 0));
+        assert!(take_key(&mut level.map, level.width, 2, 0));
+        assert_eq!(level.map, b"#   @ #\n".to_vec());
+
+        assert_eq!(solve("#&+=@#\n"), Ok(vec![Direction::Right, Direction::Right]));
+        assert_eq!(solve("#& =@#\n"), Err(Unsolved::Unreachable));
+    }
+
+    #[test]
+    fn teleporter_pair() {
+        assert_eq!(solve("#####\n#&a##\n##a@#\n#####\n"), Ok(vec![Direction::Right]));
+        assert_eq!(solve("#####\n#&a##\n##a#@\n#####\n"), Err(Unsolved::Unreachable));
+    }
+}
@@ -1,18 +1,115 @@
 extern crate termion;
+extern crate extra;
 
-use termion::{clear, cursor, style};
+mod editor;
+mod generate;
+mod level;
+mod progress;
+mod render;
+mod solver;
+
+use termion::{clear, cursor, style, terminal_size};
 use termion::raw::{IntoRawMode, RawTerminal};
+use std::env;
 use std::io::{self, Write, Read};
+use std::path::PathBuf;
+use std::process;
 use std::thread;
-use std::time;
+use std::time::{self, SystemTime, UNIX_EPOCH};
+
+use editor::Editor;
+use generate::Generator;
+use level::{Direction, End, Level};
+use progress::Progress;
+use solver::Unsolved;
 
-const MAP_1: &'static [u8] = include_bytes!("map1.txt");
-const MAP_2: &'static [u8] = include_bytes!("map2.txt");
-const MAP_3: &'static [u8] = include_bytes!("map3.txt");
-const MAP_4: &'static [u8] = include_bytes!("map4.txt");
-const MAP_5: &'static [u8] = include_bytes!("map5.txt");
 const DONE: &'static [u8] = include_bytes!("done.txt");
 
+/// The time the player takes to slide over a cell, in milliseconds.
+const SLIDE_DELAY: u64 = 10;
+/// The number of rings of sparkles spreading from the goal when it is reached.
+const SPARKLE_RINGS: i32 = 6;
+/// The time each ring of sparkles is shown, in milliseconds.
+const SPARKLE_DELAY: u64 = 50;
+
+/// The name of the progress file, in the home directory.
+const PROGRESS_FILE: &'static str = ".ice_progress";
+
+/// The help page.
+const HELP: &'static str = r#"
+ice ~ a pokemon-style ice sliding puzzle.
+
+usage:
+    ice [FILE...]          ~ play the given levels, or the built-in ones.
+    ice --level-dir DIR    ~ play the levels (.txt files) of a directory, by name.
+    ice --solve FILE       ~ print the shortest solution of a level.
+    ice --random           ~ play endless random levels.
+    ice --edit FILE        ~ edit a level, creating it if needed.
+
+flags:
+    --ascii        ~ draw the map as it is written, without colors. You are '&'.
+    --difficulty N ~ make the random levels take at least N moves (default 6). If no
+                     such level is found quickly, the hardest one found is played.
+
+rules:
+    Slide over the ice until you hit a solid block, and reach the goal (@). Try to do
+    it in as few moves as the par of the level.
+
+    The game starts on the level select, on the first level not done yet. The levels
+    done and the fewest moves they were done in are saved in ~/.ice_progress. Random
+    levels skip the level select and aren't saved.
+
+levels:
+    A level is a text file, one line per row, all rows being equally wide. You start at
+    '&', or at the second cell of the second row if the level has no '&'. The map may
+    be followed by a line "par N", giving the number of moves to aim for. Otherwise, the
+    par is the fewest moves the level can be solved in.
+
+tiles:
+    ' '             ~ ice, slid over.
+    '@'             ~ the goal.
+    '.'             ~ rough floor, stopping you on it.
+    'O'             ~ a hole, sending you back to the start of the level.
+    '<' '>' '^' 'v' ~ one-way arrows, slid over in their direction only.
+    'a' to 'z'      ~ teleporters (except 'v'), coming in pairs. Sliding into one
+                      continues from the other.
+    '$'             ~ a boulder, on ice. Sliding into it pushes it, and it slides over
+                      the ice until it hits anything else, or fills a hole it falls in.
+    '+'             ~ a key, stopping you on it. Picking it up opens every door.
+    '='             ~ a door, solid until you pick up a key.
+    anything else   ~ a solid block.
+
+controls:
+    h | a ~ slide left.
+    j | s ~ slide down.
+    k | w ~ slide up.
+    l | d ~ slide right.
+    u     ~ undo the last move.
+    r     ~ restart the level.
+    ?     ~ show the next move of the shortest way to the goal.
+    m     ~ go back to the level select.
+    q     ~ quit game.
+
+editor:
+    h j k l ~ move the cursor.
+    H J K L ~ make the map narrower, taller, shorter or wider.
+    # space ~ paint a wall or ice.
+    & @     ~ move the start, or paint a goal.
+    . O     ~ paint rough floor or a hole.
+    $       ~ paint a boulder.
+    + =     ~ paint a key or a door.
+    < > ^ v ~ paint a one-way arrow.
+    p       ~ play the level, until you quit it.
+    s       ~ save the level.
+    q       ~ quit the editor.
+
+level select:
+    j | s ~ select the next level.
+    k | w ~ select the previous level.
+    enter ~ play the selected level.
+    q     ~ quit game.
+"#;
+
 /// The game state.
 struct Game<R, W: Write> {
     /// The x coordinate.
@@ -26,42 +123,60 @@ struct Game<R, W: Write> {
     /// The width of the map.
     width: usize,
     /// The map.
-    map: &'static [u8],
+    map: Vec<u8>,
+    /// The levels to play.
+    levels: Vec<Level>,
     /// Current level.
-    level: u8,
-}
-
-/// A direction.
-#[derive(Copy, Clone)]
-enum Direction {
-    /// Up.
-    Up,
-    /// Down.
-    Down,
-    /// Left.
-    Left,
-    /// Right.
-    Right,
+    level: usize,
+    /// The generator of the next levels, if they are random.
+    generator: Option<Generator>,
+    /// The positions before each move made in the level, for undoing them, with the map before
+    /// the move if it changed, by pushing a boulder or picking up a key.
+    ///
+    /// Its length is the number of moves.
+    history: Vec<(u16, u16, Option<Vec<u8>>)>,
+    /// The levels done, or `None` if the levels are random.
+    progress: Option<Progress>,
+    /// The position the player was last drawn at, if it is still on the screen.
+    drawn: Option<(u16, u16)>,
+    /// Draw in plain ASCII, without colors?
+    ascii: bool,
 }
 
 impl<R: Read, W: Write> Game<R, W> {
     /// Construct a new game state.
-    fn new(stdin: R, stdout: W) -> Game<R, RawTerminal<W>> {
-        Game {
+    fn new(stdin: R, stdout: W, levels: Vec<Level>, generator: Option<Generator>,
+           progress: Option<Progress>, ascii: bool) -> Game<R, RawTerminal<W>> {
+        let mut game = Game {
             x: 1,
             y: 1,
             stdout: stdout.into_raw_mode().unwrap(),
             stdin: stdin,
             width: 1,
-            map: MAP_1,
+            map: Vec::new(),
+            levels: levels,
             level: 0,
-        }
+            generator: generator,
+            history: Vec::new(),
+            progress: progress,
+            drawn: None,
+            ascii: ascii,
+        };
+        game.load();
+
+        game
     }
 
     /// Start the game loop.
     ///
     /// This will listen to events and do the appropriate actions.
     fn start(&mut self) {
+        // The player is drawn, so the cursor is only in the way.
+        write!(self.stdout, "{}", cursor::Hide).unwrap();
+
+        if self.progress.is_some() && !self.select() {
+            return;
+        }
         self.init();
 
         loop {
@@ -74,6 +189,15 @@ impl<R: Read, W: Write> Game<R, W> {
                 b'j' | b's' => self.slide(Direction::Down),
                 b'k' | b'w' => self.slide(Direction::Up),
                 b'l' | b'd' => self.slide(Direction::Right),
+                b'u' => self.undo(),
+                b'r' => self.restart(),
+                b'?' => self.hint(),
+                b'm' if self.progress.is_some() => {
+                    if !self.select() {
+                        return;
+                    }
+                    self.init();
+                },
                 b'q' => return,
                 _ => {},
             }
@@ -85,113 +209,554 @@ impl<R: Read, W: Write> Game<R, W> {
     /// Initialize the level.
     fn init(&mut self) {
         write!(self.stdout, "{}{}", clear::All, cursor::Goto(1, 1)).unwrap();
+        self.drawn = None;
 
-        let mut width_counted = false;
-
-        for &i in self.map {
-            if i == b'\n' {
-                width_counted = true;
-                self.stdout.write(b"\n\r").unwrap();
-                if !width_counted {
-                    width_counted = true;
+        if self.level >= self.levels.len() {
+            // The final screen is shown as it is.
+            for &c in self.map.iter() {
+                if c == b'\n' {
+                    self.stdout.write(b"\n\r").unwrap();
+                } else {
+                    self.stdout.write(&[c]).unwrap();
                 }
-            } else {
-                self.stdout.write(&[i]).unwrap();
             }
-            if !width_counted {
-                self.width += 1;
+            return;
+        }
+
+        // Below the title bar, draw the map in one go.
+        let mut out = Vec::new();
+        write!(out, "{}", cursor::Goto(1, 2)).unwrap();
+        for &c in self.map.iter() {
+            if c == b'\n' {
+                out.extend_from_slice(b"\n\r");
+            } else {
+                out.extend_from_slice(render::tile(c, self.ascii).as_bytes());
             }
         }
-        self.update();
+        self.stdout.write_all(&out).unwrap();
+
+        self.draw_status();
     }
 
-    /// Get the position of the next step.
-    ///
-    /// This will calculate the position of the step in a given direction.
-    fn next(&mut self, dir: Direction) -> (u16, u16) {
-        let mut new_x = self.x;
-        let mut new_y = self.y;
+    /// Draw the title bar, with the level, move counter and par, and the keys below the map.
+    fn draw_status(&mut self) {
+        let mut title = vec!["ice".to_string()];
+        match self.levels.get(self.level) {
+            Some(level) => {
+                if self.generator.is_some() {
+                    title.push(format!("random level {}", self.level + 1));
+                } else {
+                    let mut name = format!("level {}/{}", self.level + 1, self.levels.len());
+                    if !level.name.is_empty() {
+                        name.push(' ');
+                        name.push_str(&level.name);
+                    }
+                    title.push(name);
+                }
+                title.push(format!("moves {}", self.history.len()));
+                if let Ok(par) = level.par {
+                    title.push(format!("par {}", par));
+                }
+            },
+            // Every level is done.
+            None => return,
+        }
+        if let Some(best) = self.best(self.level) {
+            title.push(format!("best {}", best));
+        }
+
+        let title = title.join(if self.ascii { " | " } else { " │ " });
+        write!(self.stdout, "{}{}{}",
+               cursor::Goto(1, 1), render::title(&title, self.width - 1, self.ascii),
+               clear::UntilNewline).unwrap();
 
-        match dir {
-            Direction::Right => new_x += 1,
-            Direction::Left => new_x -= 1,
-            Direction::Down => new_y += 1,
-            Direction::Up => new_y -= 1,
+        // Moving makes the hint outdated.
+        self.message("");
+        write!(self.stdout, "{}u undo, r restart, ? hint", cursor::Goto(1, self.below())).unwrap();
+        if self.progress.is_some() {
+            write!(self.stdout, ", m levels").unwrap();
         }
+        write!(self.stdout, "{}", clear::UntilNewline).unwrap();
 
-        (new_x, new_y)
+        self.update();
     }
 
-    /// Get the character of a given (x, y).
-    fn get(&mut self, x: u16, y: u16) -> u8 {
-         self.map[y as usize * self.width + x as usize]
+    /// Show a message below the keys, replacing the previous one.
+    fn message(&mut self, msg: &str) {
+        write!(self.stdout, "{}{}{}",
+               cursor::Goto(1, self.below() + 1), msg, clear::UntilNewline).unwrap();
     }
 
-    /// Move the cursor to the player position.
+    /// Get the row of the screen right below the map, where the keys are shown.
+    ///
+    /// Wide maps leave no room beside them, so the keys and messages go below. If the map is too
+    /// tall for the terminal, they are drawn over its bottom rows, rather than off the screen.
+    fn below(&self) -> u16 {
+        let (_, term_height) = terminal_size().unwrap_or((80, 24));
+        // The map is below the title bar.
+        let row = (self.map.len() / self.width) as u16 + 2;
+        row.min(term_height.saturating_sub(1).max(1))
+    }
+
+    /// Get the position on the screen of the cell at (x, y).
+    fn goto(&self, x: u16, y: u16) -> cursor::Goto {
+        // The map is below the title bar.
+        cursor::Goto(x + 1, y + 2)
+    }
+
+    /// Draw the player at its position, erasing it from where it was drawn before.
     fn update(&mut self) {
-        write!(self.stdout, "{}", cursor::Goto(self.x + 1, self.y + 1)).unwrap();
+        let mut out = String::new();
+        if let Some((x, y)) = self.drawn.take() {
+            let tile = level::get(&self.map, self.width, x, y);
+            out.push_str(&format!("{}{}", self.goto(x, y), render::tile(tile, self.ascii)));
+        }
+        if self.level < self.levels.len() {
+            let tile = level::get(&self.map, self.width, self.x, self.y);
+            out.push_str(&format!("{}{}",
+                                  self.goto(self.x, self.y), render::player(tile, self.ascii)));
+            self.drawn = Some((self.x, self.y));
+        }
+
+        self.stdout.write_all(out.as_bytes()).unwrap();
+        self.stdout.flush().unwrap();
+    }
+
+    /// Play the victory animation, moving the player on to the goal at (x, y).
+    ///
+    /// Rings of sparkles spread from the goal, one after another.
+    fn celebrate(&mut self, x: u16, y: u16) {
+        self.x = x;
+        self.y = y;
+        self.update();
+
+        let height = (self.map.len() / self.width) as i32;
+        for radius in 1..SPARKLE_RINGS + 1 {
+            // The cells at the given distance from the goal, inside the map.
+            let mut ring = Vec::new();
+            for dx in -radius..radius + 1 {
+                let dy = radius - dx.abs();
+                for &dy in [dy, -dy].iter().take(if dy == 0 { 1 } else { 2 }) {
+                    let (rx, ry) = (x as i32 + dx, y as i32 + dy);
+                    if rx >= 0 && ry >= 0 && rx + 1 < self.width as i32 && ry < height {
+                        ring.push((rx as u16, ry as u16));
+                    }
+                }
+            }
+
+            let mut sparkles = String::new();
+            let mut tiles = String::new();
+            for &(rx, ry) in ring.iter() {
+                let tile = level::get(&self.map, self.width, rx, ry);
+                sparkles.push_str(&format!("{}{}",
+                                           self.goto(rx, ry), render::sparkle(tile, self.ascii)));
+                tiles.push_str(&format!("{}{}", self.goto(rx, ry), render::tile(tile, self.ascii)));
+            }
+
+            self.stdout.write_all(sparkles.as_bytes()).unwrap();
+            self.stdout.flush().unwrap();
+            thread::sleep(time::Duration::from_millis(SPARKLE_DELAY));
+            self.stdout.write_all(tiles.as_bytes()).unwrap();
+        }
         self.stdout.flush().unwrap();
     }
 
-    /// The level is done. Go to the next level.
+    /// Get the fewest moves a level was done in, if it was done.
+    fn best(&self, level: usize) -> Option<u16> {
+        match (self.progress.as_ref(), self.levels.get(level)) {
+            (Some(progress), Some(level)) => progress.best(&level.id()),
+            _ => None,
+        }
+    }
+
+    /// Show the level select, and load the chosen level.
+    ///
+    /// Returns `false` if the player quits instead.
+    fn select(&mut self) -> bool {
+        // Start on the first level not done yet.
+        let mut selected = (0..self.levels.len())
+            .find(|&i| self.best(i).is_none())
+            .unwrap_or(0);
+        // The first level shown, for scrolling through long lists.
+        let mut top = 0;
+
+        loop {
+            let (_, height) = terminal_size().unwrap_or((80, 24));
+            // Leave room for the title and the help line.
+            let rows = (height as usize).saturating_sub(4).max(1);
+            if selected < top {
+                top = selected;
+            } else if selected >= top + rows {
+                top = selected + 1 - rows;
+            }
+
+            write!(self.stdout, "{}{} select a level:", clear::All, cursor::Goto(1, 1)).unwrap();
+            for i in top..self.levels.len().min(top + rows) {
+                let done = match self.best(i) {
+                    Some(best) => format!("done in {}", best),
+                    None => "-".to_string(),
+                };
+                let par = match self.levels[i].par {
+                    Ok(par) => format!("par {}", par),
+                    Err(why) => why.describe().to_string(),
+                };
+                write!(self.stdout, "{} {} {:3}. {:16} {:12} {}",
+                       cursor::Goto(1, (i - top) as u16 + 3),
+                       if i == selected { '>' } else { ' ' },
+                       i + 1, self.levels[i].name, done, par).unwrap();
+            }
+            write!(self.stdout, "{} j/k select, enter play, q quit{}",
+                   cursor::Goto(1, rows as u16 + 4), cursor::Goto(2, (selected - top) as u16 + 3))
+                .unwrap();
+            self.stdout.flush().unwrap();
+
+            let mut b = [0];
+            self.stdin.read(&mut b).unwrap();
+
+            match b[0] {
+                b'j' | b's' if selected + 1 < self.levels.len() => selected += 1,
+                b'k' | b'w' if selected > 0 => selected -= 1,
+                b'\r' | b'\n' => {
+                    self.level = selected;
+                    self.load();
+                    return true;
+                },
+                b'q' => return false,
+                _ => {},
+            }
+        }
+    }
+
+    /// The level is done. Save the progress, and go to the next level.
     fn done(&mut self) {
-        self.level += 1;
-        self.width = 1;
-        self.x = 1;
-        self.y = 1;
+        // The move reaching the goal is not in the history.
+        let moves = self.history.len() as u16 + 1;
+        let id = self.levels[self.level].id();
+        let failed = match self.progress {
+            Some(ref mut progress) => {
+                progress.record(&id, moves) && progress.write(&home_file(PROGRESS_FILE)).is_err()
+            },
+            None => false,
+        };
 
-        let level = self.level;
-        self.map = self.get_map(level);
+        self.level += 1;
+        self.load();
         self.init();
+
+        if failed {
+            self.message("failed to save your progress.");
+            self.update();
+        }
     }
 
-    /// Get the map of the level.
-    fn get_map(&mut self, level: u8) -> &'static [u8] {
-        match level {
-            0 => MAP_1,
-            1 => MAP_2,
-            2 => MAP_3,
-            3 => MAP_4,
-            4 => MAP_5,
-            _ => DONE,
+    /// Load the current level, or the final screen if every level is done.
+    fn load(&mut self) {
+        self.history.clear();
+
+        if self.level == self.levels.len() {
+            // Random levels never run out.
+            if let Some(ref mut generator) = self.generator {
+                self.levels.push(generator.generate());
+            }
+        }
+
+        match self.levels.get(self.level) {
+            Some(level) => {
+                self.map = level.map.clone();
+                self.width = level.width;
+                self.x = level.x;
+                self.y = level.y;
+            },
+            None => {
+                self.map = DONE.to_vec();
+                self.width = DONE.iter().position(|&c| c == b'\n').map_or(DONE.len(), |i| i + 1);
+                self.x = 1;
+                self.y = 1;
+            },
         }
     }
 
     /// Slide the character over the ices until a solid block is reached.
     fn slide(&mut self, dir: Direction) {
-        loop {
-            let (x, y) = self.next(dir);
+        let start = (self.x, self.y);
+        let map = self.map.clone();
 
-            match self.get(x, y) {
-                b'@' => {
-                    self.done();
-                    break;
-                },
-                b' ' => {
-                    self.x = x;
-                    self.y = y;
-                    self.update();
+        let (path, end) = level::slide(&self.map, self.width, self.x, self.y, dir);
+        for (x, y) in path {
+            self.x = x;
+            self.y = y;
+            self.update();
+
+            thread::sleep(time::Duration::from_millis(SLIDE_DELAY));
+        }
+
+        match end {
+            End::Stop => {
+                // A key stops the player on it, not against anything to push.
+                if !self.take_key() {
+                    self.push(dir);
+                }
+            },
+            End::Goal => {
+                let (x, y) = dir.next(self.x, self.y);
+                self.celebrate(x, y);
+                self.done();
+                return;
+            },
+            End::Fall => {
+                self.restart();
+                return;
+            },
+        }
+
+        // Bumping right into a block is not a move, unless it pushes a boulder.
+        let changed = self.map != map;
+        if (self.x, self.y) != start || changed {
+            self.history.push((start.0, start.1, if changed { Some(map) } else { None }));
+            self.draw_status();
+        }
+    }
+
+    /// Pick up the key the player stopped on, if any, redrawing the map with the doors open.
+    ///
+    /// Returns whether a key was picked up.
+    fn take_key(&mut self) -> bool {
+        let taken = level::take_key(&mut self.map, self.width, self.x, self.y);
+        if taken {
+            self.init();
+        }
+
+        taken
+    }
+
+    /// Push the boulder the player stopped against, if any, in the given direction.
+    fn push(&mut self, dir: Direction) {
+        let (x, y) = dir.next(self.x, self.y);
+        let path = level::push(&mut self.map, self.width, self.x, self.y, dir);
+
+        // Roll the boulder, the map already having it at the end.
+        let mut last = (x, y);
+        for (i, &(bx, by)) in path.iter().enumerate() {
+            let boulder = if i + 1 == path.len() {
+                level::get(&self.map, self.width, bx, by)
+            } else {
+                level::BOULDER
+            };
+            write!(self.stdout, "{}{}{}{}",
+                   self.goto(last.0, last.1), render::tile(level::ICE, self.ascii),
+                   self.goto(bx, by), render::tile(boulder, self.ascii)).unwrap();
+            self.stdout.flush().unwrap();
+            last = (bx, by);
+
+            thread::sleep(time::Duration::from_millis(SLIDE_DELAY));
+        }
+    }
 
-                    thread::sleep(time::Duration::from_millis(10));
+    /// Undo the last move of the level.
+    fn undo(&mut self) {
+        if let Some((x, y, map)) = self.history.pop() {
+            self.x = x;
+            self.y = y;
+            match map {
+                Some(map) => {
+                    // Put the boulders, keys and doors back, redrawing the whole map.
+                    self.map = map;
+                    self.init();
                 },
-                _ => break,
+                None => self.draw_status(),
             }
         }
     }
+
+    /// Show the next move of the shortest way to the goal.
+    fn hint(&mut self) {
+        if self.level >= self.levels.len() {
+            return;
+        }
+
+        let hint = match solver::solve(&self.map, self.width, self.x, self.y) {
+            Ok(moves) => format!("hint: slide {}", moves[0].name()),
+            Err(Unsolved::Unreachable) => "hint: no way to the goal, undo or restart".to_string(),
+            Err(Unsolved::TooManyMaps) => "hint: too complex to solve".to_string(),
+        };
+        self.message(&hint);
+        self.update();
+    }
+
+    /// Restart the level.
+    fn restart(&mut self) {
+        if self.level < self.levels.len() {
+            self.load();
+            self.init();
+        }
+    }
+}
+
+/// Get the path of a file in the home directory.
+fn home_file(name: &str) -> PathBuf {
+    env::var_os("HOME").map(PathBuf::from).unwrap_or_default().join(name)
 }
 
 impl<R, W: Write> Drop for Game<R, W> {
     fn drop(&mut self) {
         // When done, restore the defaults to avoid messing with the terminal.
-        write!(self.stdout, "{}{}{}", clear::All, style::Reset, cursor::Goto(1, 1)).unwrap();
+        write!(self.stdout, "{}{}{}{}",
+               clear::All, style::Reset, cursor::Goto(1, 1), cursor::Show).unwrap();
     }
 }
 
 fn main() {
+    let mut args = env::args().skip(1);
+    let mut files = Vec::new();
+    let mut level_dir = None;
+    let mut solve = None;
+    let mut edit = None;
+    let mut ascii = false;
+    let mut random = false;
+    let mut difficulty = None;
+
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                // Print the help page.
+                let stdout = io::stdout();
+                let mut stdout = stdout.lock();
+                stdout.write(HELP.as_bytes()).unwrap();
+                stdout.flush().unwrap();
+                process::exit(0);
+            },
+            "--level-dir" => level_dir = Some(PathBuf::from(args.next().unwrap_or_else(|| {
+                stderr.write(b"no level directory given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            }))),
+            "--solve" => solve = Some(PathBuf::from(args.next().unwrap_or_else(|| {
+                stderr.write(b"no level given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            }))),
+            "--edit" => edit = Some(PathBuf::from(args.next().unwrap_or_else(|| {
+                stderr.write(b"no level given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            }))),
+            "--ascii" => ascii = true,
+            "--random" => random = true,
+            "--difficulty" => difficulty = Some(args.next().unwrap_or_else(|| {
+                stderr.write(b"no difficulty given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            }).parse().unwrap_or_else(|_| {
+                stderr.write(b"invalid integer given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            })),
+            arg if arg.starts_with('-') => {
+                stderr.write(b"Unknown argument.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            },
+            file => files.push(PathBuf::from(file)),
+        }
+    }
+
+    if let Some(path) = solve {
+        // Print the shortest solution of the level.
+        let level = Level::read(&path).unwrap_or_else(|err| {
+            writeln!(stderr, "{}: {}", path.display(), err).unwrap();
+            stderr.flush().unwrap();
+            process::exit(1);
+        });
+
+        match solver::solve(&level.map, level.width, level.x, level.y) {
+            Ok(moves) => {
+                let names: Vec<&str> = moves.iter().map(|dir| dir.name()).collect();
+                println!("{} move{}: {}",
+                         moves.len(), if moves.len() == 1 { "" } else { "s" }, names.join(" "));
+                process::exit(0);
+            },
+            Err(why) => {
+                writeln!(stderr, "{}: {}", path.display(), why.describe()).unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            },
+        }
+    }
+
+    if let Some(path) = edit {
+        let stdin = io::stdin();
+        let stdout = io::stdout();
+
+        let mut editor = Editor::new(stdin.lock(), stdout.lock(), path.clone(), ascii)
+            .unwrap_or_else(|err| {
+                writeln!(stderr, "{}: {}", path.display(), err).unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            });
+        editor.start();
+        return;
+    }
+
+    if let Some(dir) = level_dir {
+        let mut listed = level::list_dir(&dir).unwrap_or_else(|err| {
+            writeln!(stderr, "{}: {}", dir.display(), err).unwrap();
+            stderr.flush().unwrap();
+            process::exit(1);
+        });
+        if listed.is_empty() {
+            writeln!(stderr, "{}: no levels (.txt files) found", dir.display()).unwrap();
+            stderr.flush().unwrap();
+            process::exit(1);
+        }
+        files.append(&mut listed);
+    }
+
+    // Load the levels before touching the terminal, so errors can be reported properly.
+    // Random levels come after the given ones, if any.
+    let levels = if files.is_empty() && !random {
+        level::builtin()
+    } else {
+        files.iter().map(|path| Level::read(path).unwrap_or_else(|err| {
+            writeln!(stderr, "{}: {}", path.display(), err).unwrap();
+            stderr.flush().unwrap();
+            process::exit(1);
+        })).collect()
+    };
+
+    let generator = if random {
+        // Pick a new seed, based on the current time.
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let seed = time.as_secs() ^ ((time.subsec_nanos() as u64) << 32);
+
+        Some(Generator::new(seed, difficulty.unwrap_or(6)))
+    } else if difficulty.is_some() {
+        stderr.write(b"--difficulty only applies to --random.\n").unwrap();
+        stderr.flush().unwrap();
+        process::exit(1);
+    } else {
+        None
+    };
+
+    // Random levels are never played twice, so there is no progress to keep.
+    let progress = if generator.is_none() {
+        let path = home_file(PROGRESS_FILE);
+        Some(Progress::read(&path).unwrap_or_else(|err| {
+            writeln!(stderr, "{}: {}", path.display(), err).unwrap();
+            stderr.flush().unwrap();
+            process::exit(1);
+        }))
+    } else {
+        None
+    };
+
     let stdin = io::stdin();
     let stdout = io::stdout();
 
-    let mut game = Game::new(stdin.lock(), stdout.lock());
+    let mut game = Game::new(stdin.lock(), stdout.lock(), levels, generator, progress,
+                             ascii);
 
     game.start();
 }
@@ -0,0 +1,78 @@
+//! The progress of the player through the levels.
+//!
+//! The progress is kept in a text file, one line per completed level, with the identifier of the
+//! level and the fewest moves it was done in separated by a tab. Levels are identified by their
+//! content (see `Level::id`), so changing a level resets its progress.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+/// The completed levels.
+pub struct Progress {
+    /// The identifier of every completed level, with the fewest moves it was done in.
+    best: Vec<(String, u16)>,
+}
+
+impl Progress {
+    /// Read the progress from a file.
+    ///
+    /// A missing file gives no progress. Malformed lines are skipped.
+    pub fn read(path: &Path) -> io::Result<Progress> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Progress { best: Vec::new() });
+            },
+            Err(err) => return Err(err),
+        };
+
+        let mut progress = Progress { best: Vec::new() };
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 2 {
+                continue;
+            }
+
+            if let Ok(moves) = fields[1].parse() {
+                progress.record(fields[0], moves);
+            }
+        }
+
+        Ok(progress)
+    }
+
+    /// Write the progress to a file.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+
+        for &(ref id, moves) in self.best.iter() {
+            writeln!(file, "{}\t{}", id, moves)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the fewest moves a level was done in, or `None` if it was never completed.
+    pub fn best(&self, id: &str) -> Option<u16> {
+        self.best.iter().find(|&&(ref i, _)| i == id).map(|&(_, moves)| moves)
+    }
+
+    /// Record a level completed in the given number of moves.
+    ///
+    /// Returns whether it is the fewest moves so far.
+    pub fn record(&mut self, id: &str, moves: u16) -> bool {
+        match self.best.iter_mut().find(|&&mut (ref i, _)| i == id) {
+            Some(&mut (_, ref mut best)) if *best <= moves => false,
+            Some(&mut (_, ref mut best)) => {
+                *best = moves;
+                true
+            },
+            None => {
+                self.best.push((id.to_string(), moves));
+                true
+            },
+        }
+    }
+}
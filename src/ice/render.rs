@@ -0,0 +1,110 @@
+//! The look of the tiles, in color or in plain ASCII.
+//!
+//! In color, every tile has a background color, on which the player and the victory sparkles are
+//! drawn. In ASCII, the map is drawn as it is written, the player being `&`.
+
+use termion::{color, style};
+
+use level::{self, Direction};
+
+/// The player, in color.
+const PLAYER: &'static str = "☻";
+/// The player, in ASCII.
+const PLAYER_ASCII: &'static str = "&";
+/// The sparkles of the victory animation, in color.
+const SPARKLE: &'static str = "✦";
+/// The sparkles of the victory animation, in ASCII.
+const SPARKLE_ASCII: &'static str = "*";
+/// The rocks, in color.
+const ROCK: &'static str = "▓";
+/// The boulders, in color.
+const BOULDER: &'static str = "●";
+/// The keys, in color.
+const KEY: &'static str = "⚷";
+/// The doors, in color.
+const DOOR: &'static str = "▥";
+
+/// Get the background color, foreground color and glyph of a tile, in color.
+fn look(c: u8) -> (String, String, String) {
+    match c {
+        level::ICE => (color::Bg(color::Cyan).to_string(), String::new(), " ".to_string()),
+        level::GOAL => (color::Bg(color::Green).to_string(),
+                        color::Fg(color::LightWhite).to_string(),
+                        "@".to_string()),
+        level::FLOOR => (color::Bg(color::Yellow).to_string(),
+                         color::Fg(color::Black).to_string(),
+                         ".".to_string()),
+        level::HOLE => (color::Bg(color::Blue).to_string(),
+                        color::Fg(color::Black).to_string(),
+                        "O".to_string()),
+        level::BOULDER => (color::Bg(color::Cyan).to_string(),
+                           color::Fg(color::Black).to_string(),
+                           BOULDER.to_string()),
+        level::KEY => (color::Bg(color::Cyan).to_string(),
+                       color::Fg(color::LightYellow).to_string(),
+                       KEY.to_string()),
+        level::DOOR => (color::Bg(color::Yellow).to_string(),
+                        color::Fg(color::Black).to_string(),
+                        DOOR.to_string()),
+        b'#' => (color::Bg(color::LightBlack).to_string(),
+                 color::Fg(color::White).to_string(),
+                 ROCK.to_string()),
+        c => match Direction::arrow(c) {
+            Some(dir) => (color::Bg(color::Cyan).to_string(),
+                          color::Fg(color::LightWhite).to_string(),
+                          match dir {
+                              Direction::Up => "↑",
+                              Direction::Down => "↓",
+                              Direction::Left => "←",
+                              Direction::Right => "→",
+                          }.to_string()),
+            None if level::is_teleporter(c) => (color::Bg(color::Magenta).to_string(),
+                                                color::Fg(color::LightWhite).to_string(),
+                                                (c as char).to_string()),
+            // Any other solid block, like the letters of the level names.
+            None => (color::Bg(color::LightBlack).to_string(),
+                     color::Fg(color::LightWhite).to_string(),
+                     (c as char).to_string()),
+        },
+    }
+}
+
+/// Draw a tile.
+pub fn tile(c: u8, ascii: bool) -> String {
+    if ascii {
+        return (c as char).to_string();
+    }
+
+    let (bg, fg, glyph) = look(c);
+    format!("{}{}{}{}{}", bg, fg, style::Bold, glyph, style::Reset)
+}
+
+/// Draw the player, standing on the given tile.
+pub fn player(c: u8, ascii: bool) -> String {
+    over(c, PLAYER, PLAYER_ASCII, color::Fg(color::Red).to_string(), ascii)
+}
+
+/// Draw a sparkle of the victory animation, over the given tile.
+pub fn sparkle(c: u8, ascii: bool) -> String {
+    over(c, SPARKLE, SPARKLE_ASCII, color::Fg(color::LightYellow).to_string(), ascii)
+}
+
+/// Draw a glyph in the given color over the background of a tile.
+fn over(c: u8, glyph: &str, glyph_ascii: &str, fg: String, ascii: bool) -> String {
+    if ascii {
+        return glyph_ascii.to_string();
+    }
+
+    let (bg, _, _) = look(c);
+    format!("{}{}{}{}{}", bg, fg, style::Bold, glyph, style::Reset)
+}
+
+/// Draw the title bar, `width` columns wide at least.
+pub fn title(text: &str, width: usize, ascii: bool) -> String {
+    let text = format!(" {:1$}", text, width.saturating_sub(1));
+    if ascii {
+        text
+    } else {
+        format!("{}{}{}", style::Invert, text, style::Reset)
+    }
+}
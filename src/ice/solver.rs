@@ -0,0 +1,103 @@
+//! A solver, finding the fewest moves to the goal.
+//!
+//! The states of the level form a graph, linked by moves. A state is the cell the player stands
+//! on, and the map as the boulders pushed and the keys picked up left it. A breadth-first search
+//! over it finds the shortest way to the goal.
+
+use std::collections::{HashMap, VecDeque};
+
+use level::{self, Direction, End, DIRECTIONS};
+
+/// The number of different maps (boulder positions and doors) searched before giving up.
+const MAX_MAPS: usize = 10000;
+
+/// Why no way to the goal was found.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Unsolved {
+    /// The goal can't be reached.
+    Unreachable,
+    /// The boulders can be pushed in too many ways to find out.
+    TooManyMaps,
+}
+
+impl Unsolved {
+    /// Describe why the level isn't solved, for the player.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Unsolved::Unreachable => "no solution",
+            Unsolved::TooManyMaps => "too complex to solve",
+        }
+    }
+}
+
+/// Find the fewest moves leading from (x, y) to the goal.
+///
+/// `width` is the length of the rows of the map, newline included.
+pub fn solve(map: &[u8], width: usize, x: u16, y: u16) -> Result<Vec<Direction>, Unsolved> {
+    let pos = |x: u16, y: u16| y as usize * width + x as usize;
+
+    // Every map met, and their number, so each one is searched once.
+    let mut maps = vec![map.to_vec()];
+    let mut ids = HashMap::new();
+    ids.insert(map.to_vec(), 0);
+
+    // The states reached, as (map, cell), each with the state it was reached from and the move
+    // made there.
+    let mut from: HashMap<(usize, usize), Option<((usize, usize), Direction)>> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    from.insert((0, pos(x, y)), None);
+    queue.push_back((0, x, y));
+
+    while let Some((id, x, y)) = queue.pop_front() {
+        for &dir in DIRECTIONS.iter() {
+            let (path, end) = level::slide(&maps[id], width, x, y, dir);
+            match end {
+                End::Stop => {
+                    let (nx, ny) = path.last().cloned().unwrap_or((x, y));
+
+                    // Pick up the key stopped on, or else push the boulder in the way, if any.
+                    let mut next = id;
+                    let (bx, by) = dir.next(nx, ny);
+                    if level::get(&maps[id], width, nx, ny) == level::KEY
+                        || level::get(&maps[id], width, bx, by) == level::BOULDER {
+                        let mut map = maps[id].clone();
+                        if level::take_key(&mut map, width, nx, ny)
+                            || !level::push(&mut map, width, nx, ny, dir).is_empty() {
+                            next = match ids.get(&map) {
+                                Some(&next) => next,
+                                None if maps.len() >= MAX_MAPS => return Err(Unsolved::TooManyMaps),
+                                None => {
+                                    ids.insert(map.clone(), maps.len());
+                                    maps.push(map);
+                                    maps.len() - 1
+                                },
+                            };
+                        }
+                    }
+
+                    if !from.contains_key(&(next, pos(nx, ny))) {
+                        from.insert((next, pos(nx, ny)), Some(((id, pos(x, y)), dir)));
+                        queue.push_back((next, nx, ny));
+                    }
+                },
+                // Falling into a hole starts over, which never helps.
+                End::Fall => {},
+                End::Goal => {
+                    // Follow the moves back to the start.
+                    let mut res = vec![dir];
+                    let mut cur = (id, pos(x, y));
+                    while let Some(&Some((prev, dir))) = from.get(&cur) {
+                        res.push(dir);
+                        cur = prev;
+                    }
+                    res.reverse();
+
+                    return Ok(res);
+                },
+            }
+        }
+    }
+
+    Err(Unsolved::Unreachable)
+}
@@ -0,0 +1,758 @@
+//! The board, independent of the terminal.
+//!
+//! The board holds the cells and the rules of the game: placing the mines, revealing cells,
+//! marking them and telling when the game is won. It doesn't draw anything, nor read any input;
+//! the game renders it and forwards the moves of the player.
+
+use std::cmp;
+use std::collections::VecDeque;
+
+use extra::rand::Randomizer;
+
+use solver::{self, Deduction, Knowledge};
+use topology::{self, Direction, Topology};
+use Options;
+
+/// A cell in the grid.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Cell {
+    /// Does it contain a mine?
+    pub mine: bool,
+    /// Is it revealed?
+    ///
+    /// That is, is it showed or chosen previously by the player?
+    pub revealed: bool,
+    /// Is this cell observed?
+    ///
+    /// That is, is the state of this cell determined, or is it pending for randomization.
+    pub observed: bool,
+    /// The mark placed on this cell by the player.
+    pub mark: Mark,
+}
+
+impl Cell {
+    /// A concealed, unobserved cell.
+    pub fn new() -> Cell {
+        Cell {
+            mine: false,
+            revealed: false,
+            observed: false,
+            mark: Mark::None,
+        }
+    }
+}
+
+/// A mark placed by the player on a concealed cell.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Mark {
+    /// No mark.
+    None,
+    /// A flag, telling that the cell holds a mine.
+    Flag,
+    /// A question mark, telling that the player is unsure about the cell.
+    ///
+    /// It is merely a reminder, and is not treated as a flag.
+    Unsure,
+}
+
+/// The outcome of revealing cells.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Reveal {
+    /// The cells revealed, in the order they were revealed.
+    pub cells: Vec<(u16, u16)>,
+    /// The mine hit, if any.
+    pub mine: Option<(u16, u16)>,
+}
+
+/// The board.
+pub struct Board {
+    /// The settings of the board.
+    ///
+    /// The seed and the autoplay setting are left to the game, and not used here.
+    options: Options,
+    /// The grid.
+    ///
+    /// The cells are enumerated like you would read a book. Left to right, until you reach the
+    /// line ending.
+    grid: Box<[Cell]>,
+    /// The shape of the grid.
+    topology: Box<dyn Topology>,
+    /// The randomizer.
+    rand: Randomizer,
+    /// Points.
+    ///
+    /// That is, revealed fields.
+    points: u16,
+}
+
+impl Board {
+    /// Create a board of concealed cells, with the randomizer seeded by `seed`.
+    pub fn new(options: Options, seed: u64) -> Board {
+        Board {
+            options: options,
+            grid: vec![Cell::new(); options.width as usize * options.height as usize]
+                .into_boxed_slice(),
+            topology: topology::new(options.width, options.height, options.wrap, options.hex),
+            rand: Randomizer::new(seed),
+            points: 0,
+        }
+    }
+
+    /// Get the settings of the board.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Get the shape of the grid.
+    pub fn topology(&self) -> &dyn Topology {
+        &*self.topology
+    }
+
+    /// Get the cells, row by row.
+    pub fn grid(&self) -> &[Cell] {
+        &self.grid
+    }
+
+    /// Get the points.
+    pub fn points(&self) -> u16 {
+        self.points
+    }
+
+    /// Get the width (number of columns) of the grid.
+    pub fn width(&self) -> u16 {
+        self.options.width
+    }
+
+    /// Get the height (number of rows) of the grid.
+    pub fn height(&self) -> u16 {
+        self.options.height
+    }
+
+    /// Get the grid position of a given coordinate.
+    pub fn pos(&self, x: u16, y: u16) -> usize {
+        y as usize * self.width() as usize + x as usize
+    }
+
+    /// Get the coordinate of a given grid position.
+    pub fn coords(&self, c: usize) -> (u16, u16) {
+        ((c % self.width() as usize) as u16, (c / self.width() as usize) as u16)
+    }
+
+    /// Conceal every cell, and reseed the randomizer.
+    pub fn reset(&mut self, seed: u64) {
+        for cell in self.grid.iter_mut() {
+            *cell = Cell::new();
+        }
+
+        self.rand = Randomizer::new(seed);
+        self.points = 0;
+    }
+
+    /// Restore the cells, the randomizer and the points of a saved game.
+    pub fn restore(&mut self, grid: Box<[Cell]>, rand: u64, points: u16) {
+        self.grid = grid;
+        self.rand = Randomizer::new(rand);
+        self.points = points;
+    }
+
+    /// Draw a new seed from the randomizer, and continue from it.
+    ///
+    /// The state of the randomizer is hidden, so this is how it is saved: a board restored with
+    /// the returned seed goes on like this one.
+    pub fn reseed(&mut self) -> u64 {
+        let mut seed = 0;
+        for _ in 0..8 {
+            seed = seed << 8 | self.rand.read_u8() as u64;
+        }
+        self.rand = Randomizer::new(seed);
+
+        seed
+    }
+
+    /// Read cell, randomizing it if it is unobserved.
+    fn read_cell(&mut self, c: usize) {
+        if !self.grid[c].observed {
+            self.grid[c].mine = self.rand.read_u8() % self.options.difficulty == 0;
+            self.grid[c].observed = true;
+        }
+    }
+
+    /// Get the cell at (x, y).
+    pub fn get(&mut self, x: u16, y: u16) -> Cell {
+        let pos = self.pos(x, y);
+
+        self.read_cell(pos);
+        self.grid[pos]
+    }
+
+    /// Get a mutable reference to the cell at (x, y).
+    fn get_mut(&mut self, x: u16, y: u16) -> &mut Cell {
+        let pos = self.pos(x, y);
+
+        self.read_cell(pos);
+        &mut self.grid[pos]
+    }
+
+    /// Calculate the cell the cursor reaches when moving from (x, y) in a given direction.
+    pub fn step(&self, x: u16, y: u16, dir: Direction) -> (u16, u16) {
+        self.topology.step(x, y, dir)
+    }
+
+    /// Get a random number below `n`.
+    pub fn rand_below(&mut self, n: usize) -> usize {
+        let mut res = 0;
+        for _ in 0..4 {
+            res = res << 8 | self.rand.read_u8() as usize;
+        }
+        res % n
+    }
+
+    /// Get the value of a cell.
+    ///
+    /// The value represent the sum of adjacent cells containing mines. A cell of value, 0, is
+    /// called "free".
+    pub fn val(&mut self, x: u16, y: u16) -> u8 {
+        // To avoid nightly version, we manually sum the adjacent mines.
+        let mut res = 0;
+        for &(x, y) in self.topology.adjacent(x, y).iter() {
+            res += self.get(x, y).mine as u8;
+        }
+        res
+    }
+
+    /// Get the number of mines left unflagged.
+    ///
+    /// This is negative if the player placed too many flags.
+    pub fn mines_left(&self) -> isize {
+        let mines = self.grid.iter().filter(|c| c.mine).count() as isize;
+        let flags = self.grid.iter().filter(|c| !c.revealed && c.mark == Mark::Flag).count();
+        let flags = flags as isize;
+        mines - flags
+    }
+
+    /// Is every cell without a mine revealed?
+    pub fn won(&self) -> bool {
+        self.grid.iter().all(|c| c.mine || c.revealed)
+    }
+
+    /// Reveal the cell at (x, y), as chosen by the player.
+    ///
+    /// If the cell holds a mine, nothing is revealed, and the mine is returned.
+    pub fn click(&mut self, x: u16, y: u16) -> Reveal {
+        if self.get(x, y).mine {
+            return Reveal {
+                cells: Vec::new(),
+                mine: Some((x, y)),
+            };
+        }
+
+        if !self.get(x, y).revealed {
+            self.points += 1;
+        }
+
+        Reveal {
+            cells: self.reveal(x, y),
+            mine: None,
+        }
+    }
+
+    /// Chord on the revealed cell at (x, y).
+    ///
+    /// If the number of adjacent flags matches the value of the cell, every unflagged neighbor is
+    /// revealed. If one of them held a mine (that is, a flag was misplaced), it is returned.
+    pub fn chord(&mut self, x: u16, y: u16) -> Reveal {
+        let mut res = Reveal {
+            cells: Vec::new(),
+            mine: None,
+        };
+
+        let mut flags = 0;
+        for &(x, y) in self.topology.adjacent(x, y).iter() {
+            let cell = self.get(x, y);
+            flags += (!cell.revealed && cell.mark == Mark::Flag) as u8;
+        }
+
+        if flags != self.val(x, y) {
+            return res;
+        }
+
+        for &(x, y) in self.topology.adjacent(x, y).iter() {
+            let cell = self.get(x, y);
+            if cell.mark != Mark::Flag && !cell.revealed {
+                let reveal = self.click(x, y);
+                res.cells.extend(reveal.cells);
+                if reveal.mine.is_some() {
+                    res.mine = reveal.mine;
+                    break;
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Set the mark of a concealed cell.
+    ///
+    /// Returns `false` if the cell is revealed, and thus can't be marked.
+    pub fn set_mark(&mut self, x: u16, y: u16, mark: Mark) -> bool {
+        // Marks say nothing about mines, so the cell is left unobserved. Observing it would draw
+        // from the randomizer, and change the board generated at the first click.
+        let pos = self.pos(x, y);
+        if self.grid[pos].revealed {
+            return false;
+        }
+
+        self.grid[pos].mark = mark;
+        true
+    }
+
+    /// Cycle the mark of a cell, from none to a flag, to unsure, and back to none.
+    ///
+    /// Returns `false` if the cell is revealed, and thus can't be marked.
+    pub fn cycle_mark(&mut self, x: u16, y: u16) -> bool {
+        let mark = match self.grid[self.pos(x, y)].mark {
+            Mark::None => Mark::Flag,
+            Mark::Flag => Mark::Unsure,
+            Mark::Unsure => Mark::None,
+        };
+        self.set_mark(x, y, mark)
+    }
+
+    /// Reveal the cell, _c_.
+    ///
+    /// This will reveal free cells, spreading until non-free cells are reached. The cells to
+    /// reveal are kept in a queue rather than recursing, so large free areas can't overflow the
+    /// stack. Revealed cells lose their marks, as misplaced flags would otherwise linger unseen.
+    /// Returns the revealed cells.
+    fn reveal(&mut self, x: u16, y: u16) -> Vec<(u16, u16)> {
+        let mut res = Vec::new();
+        let mut queue = VecDeque::new();
+
+        self.get_mut(x, y).revealed = true;
+        self.get_mut(x, y).mark = Mark::None;
+        queue.push_back((x, y));
+
+        while let Some((x, y)) = queue.pop_front() {
+            res.push((x, y));
+
+            if self.val(x, y) == 0 {
+                // Queue the adjacent cells, until a non-free cell is reached.
+                for &(x, y) in self.topology.adjacent(x, y).iter() {
+                    let cell = self.get(x, y);
+                    if !cell.revealed && !cell.mine {
+                        let cell = self.get_mut(x, y);
+                        cell.revealed = true;
+                        cell.mark = Mark::None;
+                        queue.push_back((x, y));
+                    }
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Deduce the state of a cell from what the player can see.
+    ///
+    /// Safe cells are preferred over mines, and mines which are already flagged are skipped.
+    /// Returns `None` if the player has to guess.
+    pub fn deduce(&mut self) -> Option<Deduction> {
+        let adjacency = self.adjacency();
+
+        // The flags are left out, since the player may have misplaced them.
+        let mut knowledge = Vec::with_capacity(self.grid.len());
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                knowledge.push(if self.grid[self.pos(x, y)].revealed {
+                    Knowledge::Revealed(self.val(x, y))
+                } else {
+                    Knowledge::Unknown
+                });
+            }
+        }
+
+        loop {
+            let deductions = solver::deduce(&knowledge, &adjacency);
+            if deductions.is_empty() {
+                return None;
+            }
+
+            if let Some(&safe) = deductions.iter().find(|d| match **d {
+                Deduction::Safe(_) => true,
+                Deduction::Mine(_) => false,
+            }) {
+                return Some(safe);
+            }
+
+            // Only mines were found. If the player flagged them all already, they may lead us to
+            // more cells.
+            for d in deductions {
+                if let Deduction::Mine(c) = d {
+                    if self.grid[c].mark != Mark::Flag {
+                        return Some(d);
+                    }
+                    knowledge[c] = Knowledge::Mine;
+                }
+            }
+        }
+    }
+
+    /// Calculate the adjacent cells of every cell, by their grid position.
+    ///
+    /// Unlike `Topology::adjacent`, the lists contain no duplicates, even on tiny grids.
+    fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut res = Vec::with_capacity(self.grid.len());
+
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let pos = self.pos(x, y);
+                let mut cells: Vec<usize> = self.topology.adjacent(x, y).iter()
+                    .map(|&(x, y)| self.pos(x, y))
+                    .filter(|&c| c != pos)
+                    .collect();
+                cells.sort();
+                cells.dedup();
+                res.push(cells);
+            }
+        }
+
+        res
+    }
+
+    /// Place all the mines at once, keeping (x, y) and its neighbors free of mines.
+    ///
+    /// This is done at the first click, so we know how many mines there are, and so that they
+    /// only depend on the seed.
+    pub fn generate(&mut self, x: u16, y: u16) {
+        if self.options.no_guess {
+            self.generate_no_guess(x, y);
+        } else {
+            let mut free: Vec<usize> = self.topology.adjacent(x, y).iter()
+                .map(|&(x, y)| self.pos(x, y))
+                .collect();
+            free.push(self.pos(x, y));
+
+            let mines = self.scatter_mines(&free);
+            self.place_mines(&mines);
+        }
+    }
+
+    /// Scatter the mines over the grid, keeping the `free` cells clear.
+    ///
+    /// If an exact number of mines is set, that many are placed. Otherwise, every cell holds a
+    /// mine with the probability given by the difficulty, like `read_cell` does.
+    fn scatter_mines(&mut self, free: &[usize]) -> Vec<bool> {
+        let len = self.grid.len();
+        let mut mines = vec![false; len];
+
+        match self.options.mines {
+            Some(n) => {
+                let mut cells: Vec<usize> = (0..len).filter(|c| !free.contains(c)).collect();
+                let n = cmp::min(n as usize, cells.len());
+
+                // Pick the mines by shuffling the first n cells into place (Fisher-Yates).
+                for i in 0..n {
+                    let j = i + self.rand_below(cells.len() - i);
+                    cells.swap(i, j);
+                    mines[cells[i]] = true;
+                }
+            },
+            None => {
+                for mine in mines.iter_mut() {
+                    *mine = self.rand.read_u8() % self.options.difficulty == 0;
+                }
+                for &c in free {
+                    mines[c] = false;
+                }
+            },
+        }
+
+        mines
+    }
+
+    /// Fill the grid with the given mines, marking every cell observed.
+    fn place_mines(&mut self, mines: &[bool]) {
+        for (cell, &mine) in self.grid.iter_mut().zip(mines.iter()) {
+            cell.mine = mine;
+            cell.observed = true;
+        }
+    }
+
+    /// Generate the whole grid at once, such that it can be solved without guessing.
+    ///
+    /// The mines are scattered like `generate` does, keeping (x, y) and its neighbors free. The
+    /// board is then played by the solver, and whenever it gets stuck, a mine bordering the
+    /// revealed area is moved to the unexplored part of the grid. This is repeated until the
+    /// solver clears the board.
+    ///
+    /// An exact number of mines is always kept: when a layout can't be fixed by moving mines, a
+    /// new one is scattered. Otherwise, the blocking mines are eventually removed instead.
+    fn generate_no_guess(&mut self, x: u16, y: u16) {
+        let start = self.pos(x, y);
+        let adjacency = self.adjacency();
+        let len = self.grid.len();
+
+        let mut free = adjacency[start].clone();
+        free.push(start);
+        let mut mines = self.scatter_mines(&free);
+
+        // Moving mines around may, in theory, go on forever. After this many attempts, we give
+        // up on the layout.
+        let mut attempts = len;
+        let exact = self.options.mines.is_some();
+
+        loop {
+            let knowledge = solver::play(&mines, &adjacency, start);
+
+            if (0..len).all(|c| mines[c] || knowledge[c] != Knowledge::Unknown) {
+                // Every free cell was revealed; the board is solvable.
+                break;
+            }
+
+            // The unknown cells bordering the revealed area.
+            let mut frontier = vec![false; len];
+            for (i, &k) in knowledge.iter().enumerate() {
+                if let Knowledge::Revealed(_) = k {
+                    for &c in adjacency[i].iter() {
+                        if knowledge[c] == Knowledge::Unknown {
+                            frontier[c] = true;
+                        }
+                    }
+                }
+            }
+
+            // The mines keeping the solver from progressing. Usually, these are the ones on the
+            // frontier, but free cells may also be walled in by mines the solver already found.
+            let mut blocking: Vec<usize> = (0..len).filter(|&c| frontier[c] && mines[c]).collect();
+            if blocking.is_empty() {
+                blocking = (0..len).filter(|&c| {
+                    mines[c] && adjacency[c].iter().any(|&n| {
+                        !mines[n] && knowledge[n] == Knowledge::Unknown
+                    })
+                }).collect();
+            }
+
+            if attempts == 0 {
+                if exact {
+                    // Start over with a new layout, so the number of mines stays right.
+                    mines = self.scatter_mines(&free);
+                    attempts = len;
+                    continue;
+                }
+
+                // Remove a blocking mine.
+                let from = blocking[self.rand_below(blocking.len())];
+                mines[from] = false;
+                continue;
+            }
+            attempts -= 1;
+
+            let from = blocking[self.rand_below(blocking.len())];
+            mines[from] = false;
+
+            // Move the mine to the unexplored part of the grid, or failing that, anywhere unknown
+            // outside the start area (the frontier included).
+            let mut to: Vec<usize> = (0..len).filter(|&c| {
+                knowledge[c] == Knowledge::Unknown && !frontier[c] && !mines[c] && c != from
+            }).collect();
+            if to.is_empty() {
+                to = (0..len).filter(|&c| {
+                    knowledge[c] == Knowledge::Unknown && !mines[c] && c != from
+                        && !free.contains(&c)
+                }).collect();
+            }
+
+            if to.is_empty() {
+                // There is nowhere else to put it.
+                if exact {
+                    mines = self.scatter_mines(&free);
+                    attempts = len;
+                }
+            } else {
+                mines[to[self.rand_below(to.len())]] = true;
+            }
+        }
+
+        self.place_mines(&mines);
+    }
+
+    /// Get the name of the difficulty.
+    pub fn difficulty_name(&self) -> String {
+        if let Some(mines) = self.options.mines {
+            return format!("{} mines", mines);
+        }
+
+        match self.options.difficulty {
+            2 => "god",
+            4 => "advanced",
+            6 => "intermediate",
+            10 => "beginner",
+            _ => "custom",
+        }.to_string()
+    }
+
+    /// Get the name of the board, as used in the leaderboard.
+    ///
+    /// Boards with the same name are of the same kind, so their times can be compared.
+    pub fn name(&self) -> String {
+        let mut name = format!("{}x{} {}", self.width(), self.height(), self.difficulty_name());
+        if !self.options.wrap {
+            name.push_str(" no-wrap");
+        }
+        if self.options.hex {
+            name.push_str(" hex");
+        }
+        if self.options.no_guess {
+            name.push_str(" no-guess");
+        }
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The settings of a classic beginner board.
+    fn options() -> Options {
+        Options {
+            width: 9,
+            height: 9,
+            difficulty: 6,
+            mines: Some(10),
+            wrap: false,
+            hex: false,
+            no_guess: false,
+            seed: None,
+            autoplay: false,
+        }
+    }
+
+    /// Create a board with mines on the given cells, and every cell observed.
+    fn with_mines(width: u16, height: u16, mines: &[(u16, u16)]) -> Board {
+        let options = Options {
+            width: width,
+            height: height,
+            mines: Some(mines.len() as u16),
+            ..options()
+        };
+        let mut board = Board::new(options, 0);
+
+        let mut grid = vec![Cell { observed: true, ..Cell::new() };
+                            width as usize * height as usize].into_boxed_slice();
+        for &(x, y) in mines {
+            grid[board.pos(x, y)].mine = true;
+        }
+        board.restore(grid, 0, 0);
+
+        board
+    }
+
+    /// Get the revealed cells of a board.
+    fn revealed(board: &Board) -> Vec<(u16, u16)> {
+        (0..board.grid().len()).filter(|&c| board.grid()[c].revealed)
+            .map(|c| board.coords(c))
+            .collect()
+    }
+
+    #[test]
+    fn generate_keeps_the_first_click_free() {
+        for seed in 0..50 {
+            for &(x, y) in [(0, 0), (4, 4), (8, 3)].iter() {
+                let mut board = Board::new(options(), seed);
+                board.generate(x, y);
+
+                assert!(!board.get(x, y).mine);
+                for &(x, y) in board.topology().adjacent(x, y).iter() {
+                    assert!(!board.get(x, y).mine);
+                }
+                assert_eq!(board.mines_left(), 10);
+            }
+        }
+    }
+
+    #[test]
+    fn click_reveals_the_free_region_and_its_border() {
+        // A wall of mines splits the board, the column next to it being numbered.
+        let mut board = with_mines(5, 3, &[(3, 0), (3, 1), (3, 2)]);
+
+        let reveal = board.click(0, 0);
+        assert_eq!(reveal.mine, None);
+        let mut cells = reveal.cells;
+        cells.sort();
+        assert_eq!(cells, [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        assert_eq!(revealed(&board).len(), 9);
+        assert!(!board.won());
+
+        // A numbered cell reveals only itself.
+        assert_eq!(board.click(4, 1).cells, [(4, 1)]);
+        assert!(!board.won());
+        board.click(4, 0);
+        board.click(4, 2);
+        assert!(board.won());
+
+        assert_eq!(board.click(3, 1).mine, Some((3, 1)));
+    }
+
+    #[test]
+    fn cycle_mark_goes_round_on_concealed_cells_only() {
+        let mut board = with_mines(3, 3, &[(2, 2)]);
+
+        assert!(board.cycle_mark(2, 2));
+        assert_eq!(board.get(2, 2).mark, Mark::Flag);
+        assert_eq!(board.mines_left(), 0);
+        assert!(board.cycle_mark(2, 2));
+        assert_eq!(board.get(2, 2).mark, Mark::Unsure);
+        assert_eq!(board.mines_left(), 1);
+        assert!(board.cycle_mark(2, 2));
+        assert_eq!(board.get(2, 2).mark, Mark::None);
+
+        board.click(0, 0);
+        assert!(!board.cycle_mark(0, 0));
+        assert_eq!(board.get(0, 0).mark, Mark::None);
+    }
+
+    #[test]
+    fn chord_fires_only_when_the_flags_match_the_value() {
+        let mut board = with_mines(5, 3, &[(3, 0), (3, 1), (3, 2)]);
+        board.click(2, 1);
+        assert_eq!(board.val(2, 1), 3);
+
+        // Too few flags.
+        board.set_mark(3, 0, Mark::Flag);
+        board.set_mark(3, 1, Mark::Flag);
+        assert!(board.chord(2, 1).cells.is_empty());
+        assert_eq!(revealed(&board), [(2, 1)]);
+
+        // Too many flags.
+        board.set_mark(3, 2, Mark::Flag);
+        board.set_mark(1, 1, Mark::Flag);
+        assert!(board.chord(2, 1).cells.is_empty());
+        assert_eq!(revealed(&board), [(2, 1)]);
+
+        // Just enough, revealing the unflagged neighbors and the free cells beyond them.
+        board.set_mark(1, 1, Mark::None);
+        let reveal = board.chord(2, 1);
+        assert_eq!(reveal.mine, None);
+        assert_eq!(revealed(&board).len(), 9);
+
+        // A misplaced flag makes the chord hit a mine.
+        let mut board = with_mines(5, 3, &[(3, 0), (3, 1), (3, 2)]);
+        board.click(2, 1);
+        board.set_mark(3, 0, Mark::Flag);
+        board.set_mark(3, 1, Mark::Flag);
+        board.set_mark(2, 2, Mark::Flag);
+        assert_eq!(board.chord(2, 1).mine, Some((3, 2)));
+    }
+
+    #[test]
+    fn click_reveals_a_huge_free_board() {
+        // Revealing recursively would overflow the stack here.
+        let options = Options { width: 500, height: 500, mines: Some(0), ..options() };
+        let mut board = Board::new(options, 0);
+        board.generate(0, 0);
+
+        assert_eq!(board.click(0, 0).cells.len(), 500 * 500);
+        assert!(board.won());
+    }
+}
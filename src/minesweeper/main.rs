@@ -4,36 +4,35 @@
 extern crate termion;
 extern crate extra;
 
-use termion::{clear, cursor, color, style};
+mod board;
+mod save;
+mod scores;
+mod solver;
+mod topology;
+
+use termion::{async_stdin, clear, cursor, color, style};
 use termion::raw::IntoRawMode;
-use termion::input::TermRead;
-use termion::event::Key;
+use termion::input::{MouseTerminal, TermRead};
+use termion::event::{Event, Key, MouseButton, MouseEvent};
 
 use std::env;
+use std::fs;
 use std::io::{self, Read, Write};
+use std::path::PathBuf;
 use std::process;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use extra::rand::Randomizer;
-
-/// A cell in the grid.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
-struct Cell {
-    /// Does it contain a mine?
-    mine: bool,
-    /// Is it revealed?
-    ///
-    /// That is, is it showed or chosen previously by the player?
-    revealed: bool,
-    /// Is this cell observed?
-    ///
-    /// That is, is the state of this cell determined, or is it pending for randomization.
-    observed: bool,
-    /// Does this flag contain a flag?
-    flagged: bool,
-}
+use board::{Board, Mark};
+use save::Save;
+use scores::{Score, Scores};
+use solver::Deduction;
+use topology::Direction;
 
 /// The string printed for flagged cells.
 const FLAGGED: &'static str = "F";
+/// The string printed for cells marked unsure.
+const UNSURE: &'static str = "?";
 /// The string printed for mines in the game over revealing.
 const MINE: &'static str = "*";
 /// The string printed for concealed cells.
@@ -46,6 +45,13 @@ const GAME_OVER: &'static str = "╔══════════════
                                  ║ q ┆ quit        ║\n\r\
                                  ╚═══╧═════════════╝";
 
+/// The victory screen.
+const VICTORY: &'static str = "╔═════════════════╗\n\r\
+                               ║───┬You won!─────║\n\r\
+                               ║ r ┆ replay      ║\n\r\
+                               ║ q ┆ quit        ║\n\r\
+                               ╚═══╧═════════════╝";
+
 /// The upper and lower boundary char.
 const HORZ_BOUNDARY: &'static str = "─";
 /// The left and right boundary char.
@@ -67,7 +73,15 @@ minesweeper ~ a simple minesweeper implementation.
 rules:
     Select a cell to reveal, printing the number of adjacent cells holding a mine.
     If no adjacent cells hold a mine, the cell is called free. Free cell will recursively
-    reveal their neighboring cells. If a mine is revealed, you loose. The grid wraps.
+    reveal their neighboring cells. If a mine is revealed, you loose. The grid wraps,
+    unless --no-wrap is given. With --hex, the cells are hexagonal, and only have six
+    neighbors.
+    Selecting a revealed cell with as many adjacent flags as its number reveals all of
+    its unflagged neighbors (chording). You win when every cell without a mine is
+    revealed. The status line shows the time, and the mines left unflagged.
+    Every board is generated from a seed, shown when the game ends. Given the same seed
+    and first click, the board is the same, so you can race your friends on it.
+    The best times of each kind of board are kept in a leaderboard.
 
 flags:
     -r | --height N ~ set the height of the grid.
@@ -77,49 +91,120 @@ flags:
     -i              ~ intermediate mode.
     -a              ~ advanced mode.
     -g              ~ god mode.
+    --mines N       ~ place exactly N mines, instead of using the mode's probability.
+    --beginner      ~ classic beginner board: 9x9 with 10 mines, not wrapping.
+    --intermediate  ~ classic intermediate board: 16x16 with 40 mines, not wrapping.
+    --expert        ~ classic expert board: 30x16 with 99 mines, not wrapping.
+    -n | --no-guess ~ only generate boards which can be solved without guessing.
+    --no-wrap       ~ don't wrap around the edges of the grid.
+    --hex           ~ play on a grid of hexagonal cells.
+    --load FILE     ~ resume a saved game. The file is removed, and written again if
+                      you quit before the game ends.
+    --seed N        ~ generate the boards from a given seed.
+    --autoplay      ~ watch the game solve itself.
+    --scores        ~ show the best times, and exit.
 
 controls:
     ---selection--------------------
-    space ~ reveal the current cell.
+    space ~ reveal the current cell, or chord on a revealed one.
+    ---mouse------------------------
+    left click   ~ reveal a cell.
+    right click  ~ cycle the mark.
+    middle click ~ chord on a revealed cell.
     ---movement---------------------
     h | a ~ move left.
     j | s ~ move down.
     k | w ~ move up.
     l | d ~ move right.
-    ---flags------------------------
-    f     ~ set flag.
-    F     ~ remove flag.
+    ---marks------------------------
+    f     ~ cycle the mark: flag, unsure (?), none.
+    F     ~ remove the mark.
+    ---assistance-------------------
+    ?     ~ show a safe cell or a mine, if one can be deduced.
     ---control----------------------
-    q     ~ quit game.
+    q     ~ quit game, saving it if unfinished.
     r     ~ restart game.
 
 author:
     ticki.
 "#;
 
-/// The game state.
-struct Game<R, W: Write> {
+/// The name of the file unfinished games are saved to, in the home directory.
+///
+/// If a game was loaded, it is saved back to the file it was loaded from instead. If the file
+/// holds another game already, a number is appended to the name (e.g. `.minesweeper_save-2`).
+const SAVE_FILE: &'static str = ".minesweeper_save";
+/// The name of the leaderboard file, in the home directory.
+const SCORES_FILE: &'static str = ".minesweeper_scores";
+
+/// The time between two moves in autoplay mode.
+const AUTOPLAY_DELAY: u64 = 100;
+
+/// The classic beginner board, as (width, height, mines).
+const BEGINNER: (u16, u16, u16) = (9, 9, 10);
+/// The classic intermediate board, as (width, height, mines).
+const INTERMEDIATE: (u16, u16, u16) = (16, 16, 40);
+/// The classic expert board, as (width, height, mines).
+const EXPERT: (u16, u16, u16) = (30, 16, 99);
+
+/// The settings of a game.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Options {
     /// Width of the grid.
     width: u16,
-    /// The grid.
-    ///
-    /// The cells are enumerated like you would read a book. Left to right, until you reach the
-    /// line ending.
-    grid: Box<[Cell]>,
+    /// Height of the grid.
+    height: u16,
     /// The difficulty of the game.
-    ///
-    /// The lower, the easier.
     difficulty: u8,
+    /// The exact number of mines, if any.
+    ///
+    /// If not set, the number of mines is random, as given by the difficulty.
+    mines: Option<u16>,
+    /// Does the grid wrap around its edges?
+    wrap: bool,
+    /// Are the cells hexagonal?
+    hex: bool,
+    /// Should the board be solvable without guessing?
+    no_guess: bool,
+    /// The seed to generate the boards from, if any.
+    seed: Option<u64>,
+    /// Should the game play itself?
+    autoplay: bool,
+}
+
+/// The game state.
+struct Game<R, W: Write> {
+    /// The board.
+    board: Board,
     /// The x coordinate.
     x: u16,
     /// The y coordinate.
     y: u16,
-    /// The randomizer.
-    rand: Randomizer,
-    /// Points.
+    /// The seed the board is generated from.
+    seed: u64,
+    /// Was the seed given by the player?
+    ///
+    /// If so, replaying gives the same board. Otherwise, a new seed is picked.
+    fixed_seed: bool,
+    /// When the first cell was revealed, starting the clock.
     ///
-    /// That is, revealed fields.
-    points: u16,
+    /// The mines are all placed at this point.
+    started: Option<Instant>,
+    /// When the game ended, stopping the clock.
+    stopped: Option<Instant>,
+    /// The status line, as it was last printed.
+    status: String,
+    /// A message to show in the status line.
+    message: &'static str,
+    /// The cell highlighted by the last hint, if any.
+    hint: Option<(u16, u16)>,
+    /// Does the game play itself?
+    autoplay: bool,
+    /// Did the autoplayer make moves in this game?
+    ///
+    /// Unlike `autoplay`, this stays set when the autoplayer gives up, so a game it played isn't
+    /// recorded as the player's.
+    autoplayed: bool,
     /// Standard output.
     stdout: W,
     /// Standard input.
@@ -127,32 +212,47 @@ struct Game<R, W: Write> {
 }
 
 /// Initialize the game.
-fn init<W: Write, R: Read>(mut stdout: W, stdin: R, difficulty: u8, w: u16, h: u16) {
+///
+/// If `save` is given, that game is resumed. When the player quits, the game is returned if it
+/// was left unfinished.
+fn init<W: Write, R: Read>(mut stdout: W, stdin: R, options: Options, save: Option<Save>)
+                           -> Option<Save> {
     write!(stdout, "{}", clear::All).unwrap();
 
     // Set the initial game state.
     let mut game = Game {
+        board: Board::new(options, 0),
         x: 0,
         y: 0,
-        rand: Randomizer::new(0),
-        width: w,
-        grid: vec![Cell {
-            mine: false,
-            revealed: false,
-            observed: false,
-            flagged: false,
-        }; w as usize * h as usize].into_boxed_slice(),
-        points: 0,
-        stdin: stdin.keys(),
+        seed: options.seed.unwrap_or(0),
+        fixed_seed: options.seed.is_some(),
+        started: None,
+        stopped: None,
+        status: String::new(),
+        message: "",
+        hint: None,
+        autoplay: options.autoplay,
+        autoplayed: options.autoplay,
+        stdin: stdin.events(),
         stdout: stdout,
-        difficulty: difficulty,
     };
 
-    // Reset that game.
-    game.reset();
+    if let Some(save) = save {
+        // Pick up where we left.
+        game.restore(save);
+    } else {
+        // Reset that game.
+        game.reset();
+    }
 
     // Start the event loop.
     game.start();
+
+    if game.started.is_some() && game.stopped.is_none() {
+        Some(game.save())
+    } else {
+        None
+    }
 }
 
 impl<R, W: Write> Drop for Game<R, W> {
@@ -162,74 +262,47 @@ impl<R, W: Write> Drop for Game<R, W> {
     }
 }
 
-impl<R: Iterator<Item=Result<Key, std::io::Error>>, W: Write> Game<R, W> {
-    /// Get the grid position of a given coordinate.
-    fn pos(&self, x: u16, y: u16) -> usize {
-        y as usize * self.width as usize + x as usize
-    }
-
-    /// Read cell, randomizing it if it is unobserved.
-    fn read_cell(&mut self, c: usize) {
-        if !self.grid[c].observed {
-            self.grid[c].mine = self.rand.read_u8() % self.difficulty == 0;
-            self.grid[c].observed = true;
-        }
-    }
-
-    /// Get the cell at (x, y).
-    fn get(&mut self, x: u16, y: u16) -> Cell {
-        let pos = self.pos(x, y);
-
-        self.read_cell(pos);
-        self.grid[pos]
-    }
-
-    /// Get a mutable reference to the cell at (x, y).
-    fn get_mut(&mut self, x: u16, y: u16) -> &mut Cell {
-        let pos = self.pos(x, y);
-
-        self.read_cell(pos);
-        &mut self.grid[pos]
-    }
-
+impl<R: Iterator<Item=Result<Event, std::io::Error>>, W: Write> Game<R, W> {
     /// Start the game loop.
     ///
     /// This will listen to events and do the appropriate actions.
     fn start(&mut self) {
-        let mut first_click = true;
         loop {
-            // Read a single byte from stdin.
-            let b = self.stdin.next().unwrap().unwrap();
+            // Read a single key from stdin.
+            let b = self.next_key();
             use termion::event::Key::*;
-            if let Char(c) = b {
-                // Collect it as entropy.
-                self.rand.write_u8(c as u8);
+            if b != Char('?') {
+                self.clear_hint();
             }
             match b {
-                Char('h') | Char('a') | Left  => self.x = self.left(self.x),
-                Char('j') | Char('s') | Down  => self.y = self.down(self.y),
-                Char('k') | Char('w') | Up    => self.y = self.up(self.y),
-                Char('l') | Char('d') | Right => self.x = self.right(self.x),
+                Char('h') | Char('a') | Left  => self.step(Direction::Left),
+                Char('j') | Char('s') | Down  => self.step(Direction::Down),
+                Char('k') | Char('w') | Up    => self.step(Direction::Up),
+                Char('l') | Char('d') | Right => self.step(Direction::Right),
                 Char(' ') => {
                     // Check if it was a mine.
                     let (x, y) = (self.x, self.y);
 
-                    if first_click {
-                        // This is the player's first turn; clear all cells of
-                        // mines around the cursor.
-                        for &(x, y) in self.adjacent(x, y).iter() {
-                            self.get_mut(x, y).mine = false;
-                        }
-                        self.get_mut(x, y).mine = false;
-                        first_click = false;
+                    if self.started.is_none() {
+                        // This is the player's first turn; place all the mines at once.
+                        self.board.generate(x, y);
+                        self.started = Some(Instant::now());
                     }
 
-                    if self.get(x, y).mine {
+                    let reveal = if self.board.get(x, y).revealed {
+                        self.board.chord(x, y)
+                    } else {
+                        self.board.click(x, y)
+                    };
+                    self.draw_cells(&reveal.cells);
+
+                    if let Some((x, y)) = reveal.mine {
                         self.reveal_all();
                         // Make the background colour of the mine we just
                         // landed on red, and the foreground black.
+                        let goto = self.goto(x, y);
                         write!(self.stdout, "{}{}{}{}{}",
-                               cursor::Goto(x + 2, y + 2),
+                               goto,
                                color::Bg(color::Red), color::Fg(color::Black),
                                MINE,
                                style::Reset).unwrap();
@@ -237,19 +310,26 @@ impl<R: Iterator<Item=Result<Key, std::io::Error>>, W: Write> Game<R, W> {
                         return;
                     }
 
-                    if !self.get(x, y).revealed {
-                        self.points += 1;
+                    if self.board.won() {
+                        self.victory();
+                        return;
                     }
 
-                    // Reveal the cell.
-                    self.reveal(x, y);
-
-                    self.print_points();
+                    self.print_status();
                 },
                 Char('f') => {
                     let (x, y) = (self.x, self.y);
-                    self.toggle_flag(x, y);
+                    if self.board.cycle_mark(x, y) {
+                        self.draw_cell(x, y);
+                    }
                 }
+                Char('F') => {
+                    let (x, y) = (self.x, self.y);
+                    if self.board.set_mark(x, y, Mark::None) {
+                        self.draw_cell(x, y);
+                    }
+                }
+                Char('?') => self.show_hint(),
                 Char('r') => {
                     self.restart();
                     return;
@@ -259,54 +339,229 @@ impl<R: Iterator<Item=Result<Key, std::io::Error>>, W: Write> Game<R, W> {
             }
 
             // Make sure the cursor is placed on the current position.
-            write!(self.stdout, "{}", cursor::Goto(self.x + 2, self.y + 2)).unwrap();
+            let goto = self.goto(self.x, self.y);
+            write!(self.stdout, "{}", goto).unwrap();
             self.stdout.flush().unwrap();
         }
     }
 
-    /// Set a flag on cell.
-    fn set_flag(&mut self, x: u16, y: u16) {
-        if !self.get(x, y).revealed {
-            self.stdout.write(FLAGGED.as_bytes()).unwrap();
-            self.get_mut(x, y).flagged = true;
+    /// Move the cursor one cell in the given direction.
+    fn step(&mut self, dir: Direction) {
+        let (x, y) = self.board.step(self.x, self.y, dir);
+        self.x = x;
+        self.y = y;
+    }
+
+    /// Get the terminal cursor movement to the cell at (x, y).
+    fn goto(&self, x: u16, y: u16) -> cursor::Goto {
+        let (column, row) = self.board.topology().screen_pos(x, y);
+        // Skip the frame, and convert to the 1-based coordinates of the terminal.
+        cursor::Goto(column + 2, row + 2)
+    }
+
+    /// Wait for the next key.
+    ///
+    /// Mouse clicks on the grid move the cursor to the clicked cell, and are then treated like
+    /// the matching key. The status line is kept up to date in the meantime, so the clock keeps
+    /// ticking.
+    fn next_key(&mut self) -> Key {
+        loop {
+            match self.stdin.next() {
+                Some(event) => match event.unwrap() {
+                    Event::Key(key) => return key,
+                    Event::Mouse(MouseEvent::Press(button, x, y)) => {
+                        if let Some(key) = self.click_key(button, x, y) {
+                            return key;
+                        }
+                    },
+                    _ => {},
+                },
+                None if self.autoplay && self.stopped.is_none() => {
+                    // Give the player a chance to follow, then make a move.
+                    self.print_status();
+                    thread::sleep(Duration::from_millis(AUTOPLAY_DELAY));
+                    return self.autoplay_key();
+                },
+                None => {
+                    self.print_status();
+                    thread::sleep(Duration::from_millis(50));
+                },
+            }
         }
     }
-    /// Remove a flag on cell.
-    fn remove_flag(&mut self, x: u16, y: u16) {
-        self.stdout.write(CONCEALED.as_bytes()).unwrap();
-        self.get_mut(x, y).flagged = false;
+
+    /// Get the key matching a mouse click at the terminal coordinates (x, y).
+    ///
+    /// If the click hit the grid, the cursor is moved to the clicked cell.
+    fn click_key(&mut self, button: MouseButton, x: u16, y: u16) -> Option<Key> {
+        // The grid is drawn from `Goto(2, 2)`, inside the frame.
+        if x < 2 || y < 2 {
+            return None;
+        }
+        let (x, y) = match self.board.topology().cell_at(x - 2, y - 2) {
+            Some(cell) => cell,
+            None => return None,
+        };
+
+        let key = match button {
+            MouseButton::Left => Key::Char(' '),
+            MouseButton::Right => Key::Char('f'),
+            // Chording is only done on revealed cells.
+            MouseButton::Middle if self.board.grid()[self.board.pos(x, y)].revealed => {
+                Key::Char(' ')
+            },
+            _ => return None,
+        };
+
+        self.x = x;
+        self.y = y;
+        Some(key)
     }
-    /// Place a flag on cell if unflagged, or remove it if present.
-    fn toggle_flag(&mut self, x: u16, y: u16) {
-        if !self.get(x, y).flagged {
-            self.set_flag(x, y);
-        } else {
-            self.remove_flag(x, y);
+
+    /// Pick the next move in autoplay mode, moving the cursor to the cell to play.
+    ///
+    /// The move is given as the key the player would press. When nothing can be deduced, a
+    /// random concealed cell is revealed.
+    fn autoplay_key(&mut self) -> Key {
+        let (c, key) = match self.board.deduce() {
+            Some(Deduction::Safe(c)) => (c, Key::Char(' ')),
+            // Cycling the mark of an unsure cell would remove it, so we clear it first.
+            Some(Deduction::Mine(c)) if self.board.grid()[c].mark == Mark::Unsure => {
+                (c, Key::Char('F'))
+            },
+            Some(Deduction::Mine(c)) => (c, Key::Char('f')),
+            None => {
+                let concealed: Vec<usize> = self.board.grid().iter()
+                    .enumerate()
+                    .filter(|&(_, cell)| !cell.revealed && cell.mark != Mark::Flag)
+                    .map(|(c, _)| c)
+                    .collect();
+                if concealed.is_empty() {
+                    // Only wrongly placed flags are left. Nothing we can do.
+                    self.autoplay = false;
+                    return Key::Null;
+                }
+                (concealed[self.board.rand_below(concealed.len())], Key::Char(' '))
+            },
+        };
+
+        let (x, y) = self.board.coords(c);
+        self.x = x;
+        self.y = y;
+        key
+    }
+
+    /// Highlight a safe cell or a mine, and move the cursor there.
+    fn show_hint(&mut self) {
+        self.clear_hint();
+
+        let (c, bg, message) = match self.board.deduce() {
+            Some(Deduction::Safe(c)) => {
+                (c, color::Bg(color::Green).to_string(), "hint: this cell is safe")
+            },
+            Some(Deduction::Mine(c)) => {
+                (c, color::Bg(color::Red).to_string(), "hint: this cell is a mine")
+            },
+            None => {
+                self.message = "hint: you will have to guess";
+                return;
+            },
+        };
+
+        let (x, y) = self.board.coords(c);
+        self.x = x;
+        self.y = y;
+
+        write!(self.stdout, "{}{}", bg, color::Fg(color::Black)).unwrap();
+        self.draw_cell(x, y);
+        write!(self.stdout, "{}", style::Reset).unwrap();
+
+        self.hint = Some((x, y));
+        self.message = message;
+    }
+
+    /// Remove the highlighting of the last hint.
+    fn clear_hint(&mut self) {
+        if let Some((x, y)) = self.hint.take() {
+            self.draw_cell(x, y);
         }
+        self.message = "";
     }
 
     /// Reset the game.
     ///
     /// This will display the starting grid, and fill the old grid with random mines.
     fn reset(&mut self) {
+        if !self.fixed_seed {
+            self.seed = new_seed();
+        }
+        self.board.reset(self.seed);
+        self.autoplayed = self.autoplay;
+
+        self.started = None;
+        self.stopped = None;
+        self.draw();
+    }
+
+    /// Resume a saved game.
+    fn restore(&mut self, save: Save) {
+        self.x = save.x;
+        self.y = save.y;
+        self.seed = save.seed;
+        self.board.restore(save.grid, save.rand, save.points);
+        self.autoplayed = self.autoplay || save.autoplayed;
+        // Set the clock back by the time already played.
+        let now = Instant::now();
+        self.started = Some(now.checked_sub(Duration::from_secs(save.time)).unwrap_or(now));
+        self.stopped = None;
+        self.draw();
+    }
+
+    /// Save the game, so it can be resumed later.
+    fn save(&mut self) -> Save {
+        let time = match self.started {
+            Some(started) => started.elapsed().as_secs(),
+            None => 0,
+        };
+
+        Save {
+            options: Options {
+                seed: if self.fixed_seed { Some(self.seed) } else { None },
+                autoplay: self.autoplay,
+                .. *self.board.options()
+            },
+            seed: self.seed,
+            rand: self.board.reseed(),
+            x: self.x,
+            y: self.y,
+            points: self.board.points(),
+            time: time,
+            autoplayed: self.autoplayed,
+            grid: self.board.grid().to_vec().into_boxed_slice(),
+        }
+    }
+
+    /// Draw the frame and the grid, as the player sees it.
+    fn draw(&mut self) {
         // Reset the cursor.
         write!(self.stdout, "{}", cursor::Goto(1, 1)).unwrap();
 
         // Write the upper part of the frame.
+        let width = self.board.topology().screen_width();
         self.stdout.write(TOP_LEFT_CORNER.as_bytes()).unwrap();
-        for _ in 0..self.width {
+        for _ in 0..width {
             self.stdout.write(HORZ_BOUNDARY.as_bytes()).unwrap();
         }
         self.stdout.write(TOP_RIGHT_CORNER.as_bytes()).unwrap();
         self.stdout.write(b"\n\r").unwrap();
 
-        // Conceal all the cells.
-        for _ in 0..self.height() {
+        for _ in 0..self.board.height() {
             // The left part of the frame
             self.stdout.write(VERT_BOUNDARY.as_bytes()).unwrap();
 
-            for _ in 0..self.width {
-                self.stdout.write_all(CONCEALED.as_bytes()).unwrap();
+            // Blank the row, since hexagonal cells leave gaps between them.
+            for _ in 0..width {
+                self.stdout.write(b" ").unwrap();
             }
 
             // The right part of the frame.
@@ -316,83 +571,107 @@ impl<R: Iterator<Item=Result<Key, std::io::Error>>, W: Write> Game<R, W> {
 
         // Write the lower part of the frame.
         self.stdout.write(BOTTOM_LEFT_CORNER.as_bytes()).unwrap();
-        for _ in 0..self.width {
+        for _ in 0..width {
             self.stdout.write(HORZ_BOUNDARY.as_bytes()).unwrap();
         }
         self.stdout.write(BOTTOM_RIGHT_CORNER.as_bytes()).unwrap();
 
-        write!(self.stdout, "{}", cursor::Goto(self.x + 2, self.y + 2)).unwrap();
-        self.stdout.flush().unwrap();
-
-        // Reset the grid.
-        for i in 0..self.grid.len() {
-            // Fill it with random, concealed fields.
-            self.grid[i] = Cell {
-                mine: false,
-                revealed: false,
-                observed: false,
-                flagged: false,
-            };
-
-            self.points = 0;
+        let mut cells = Vec::with_capacity(self.board.grid().len());
+        for y in 0..self.board.height() {
+            for x in 0..self.board.width() {
+                cells.push((x, y));
+            }
         }
+        self.draw_cells(&cells);
+
+        self.status.clear();
+        self.print_status();
     }
 
-    /// Get the value of a cell.
-    ///
-    /// The value represent the sum of adjacent cells containing mines. A cell of value, 0, is
-    /// called "free".
-    fn val(&mut self, x: u16, y: u16) -> u8 {
-        // To avoid nightly version, we manually sum the adjacent mines.
-        let mut res = 0;
-        for &(x, y) in self.adjacent(x, y).iter() {
-            res += self.get(x, y).mine as u8;
-        }
-        res
+    /// Draw the cell at (x, y), as the player sees it.
+    fn draw_cell(&mut self, x: u16, y: u16) {
+        self.draw_cells(&[(x, y)]);
     }
 
-    /// Reveal the cell, _c_.
+    /// Draw the given cells, as the player sees them.
     ///
-    /// This will recursively reveal free cells, until non-free cell is reached, terminating the
-    /// current recursion descendant.
-    fn reveal(&mut self, x: u16, y: u16) {
-        let v = self.val(x, y);
-
-        self.get_mut(x, y).revealed = true;
-
-        write!(self.stdout, "{}", cursor::Goto(x + 2, y + 2)).unwrap();
-
-        if v == 0 {
-            // If the cell is free, simply put a space on the position.
-            self.stdout.write(b" ").unwrap();
-
-            // Recursively reveal adjacent cells until a non-free cel is reached.
-            for &(x, y) in self.adjacent(x, y).iter() {
-                if !self.get(x, y).revealed && !self.get(x, y).mine {
-                    self.reveal(x, y);
+    /// The output is written in one go, so large free areas are drawn quickly.
+    fn draw_cells(&mut self, cells: &[(u16, u16)]) {
+        let mut out = Vec::new();
+
+        for &(x, y) in cells {
+            write!(out, "{}", self.goto(x, y)).unwrap();
+
+            let cell = self.board.grid()[self.board.pos(x, y)];
+            if cell.revealed {
+                match self.board.val(x, y) {
+                    // If the cell is free, simply put a space on the position.
+                    0 => out.push(b' '),
+                    v => out.push(b'0' + v),
                 }
+            } else if cell.mark == Mark::Flag {
+                out.extend_from_slice(FLAGGED.as_bytes());
+            } else if cell.mark == Mark::Unsure {
+                write!(out, "{}{}{}", color::Fg(color::Yellow), UNSURE, color::Fg(color::Reset))
+                    .unwrap();
+            } else {
+                out.extend_from_slice(CONCEALED.as_bytes());
             }
-        } else {
-            // Aww. The cell was not free. Print the value instead.
-            self.stdout.write(&[b'0' + v]).unwrap();
         }
+
+        self.stdout.write_all(&out).unwrap();
     }
 
-    /// Print the point count.
-    fn print_points(&mut self) {
-        let height = self.height();
-        write!(self.stdout, "{}", cursor::Goto(3, height + 2)).unwrap();
-        self.stdout.write(self.points.to_string().as_bytes()).unwrap();
+    /// Print the status line below the grid.
+    ///
+    /// This shows the elapsed time, the number of mines left unflagged, the point count and the
+    /// difficulty. Nothing is written if the line didn't change since last time.
+    fn print_status(&mut self) {
+        let secs = match self.started {
+            Some(started) => {
+                let now = self.stopped.unwrap_or_else(Instant::now);
+                now.duration_since(started).as_secs()
+            },
+            None => 0,
+        };
+        let mines_left = if self.started.is_some() {
+            self.board.mines_left().to_string()
+        } else {
+            // The mines are not placed before the first click.
+            "?".to_string()
+        };
+
+        let mut status = format!("time {} │ mines left {} │ points {} │ {}",
+                                 format_time(secs), mines_left, self.board.points(),
+                                 self.board.difficulty_name());
+        if self.autoplay {
+            status.push_str(" │ autoplay");
+        }
+        if !self.message.is_empty() {
+            status.push_str(" │ ");
+            status.push_str(self.message);
+        }
+        if status == self.status {
+            return;
+        }
+
+        let height = self.board.height();
+        let goto = self.goto(self.x, self.y);
+        write!(self.stdout, "{}{}{}{}",
+               cursor::Goto(1, height + 3), clear::CurrentLine, status, goto).unwrap();
+        self.stdout.flush().unwrap();
+        self.status = status;
     }
 
     /// Reveal all the fields, printing where the mines were.
     fn reveal_all(&mut self) {
         write!(self.stdout, "{}", cursor::Goto(1, 1)).unwrap();
 
-        for y in 0..self.height() {
-            for x in 0..self.width {
-                write!(self.stdout, "{}", cursor::Goto(x + 2, y + 2)).unwrap();
-                if self.get(x, y).mine {
+        for y in 0..self.board.height() {
+            for x in 0..self.board.width() {
+                let goto = self.goto(x, y);
+                write!(self.stdout, "{}", goto).unwrap();
+                if self.board.get(x, y).mine {
                     self.stdout.write(MINE.as_bytes()).unwrap();
                 }
             }
@@ -401,15 +680,77 @@ impl<R: Iterator<Item=Result<Key, std::io::Error>>, W: Write> Game<R, W> {
 
     /// Game over!
     fn game_over(&mut self) {
+        self.end(GAME_OVER, &[]);
+    }
+
+    /// The player won!
+    fn victory(&mut self) {
+        self.stopped = Some(Instant::now());
+        let scores = self.record_score();
+        self.end(VICTORY, &scores);
+    }
+
+    /// Add the time of the game just won to the leaderboard.
+    ///
+    /// Returns the lines to show on the victory screen. Games won by the autoplayer are not
+    /// recorded.
+    fn record_score(&mut self) -> Vec<String> {
+        let (started, stopped) = match (self.started, self.stopped) {
+            (Some(started), Some(stopped)) if !self.autoplayed => (started, stopped),
+            _ => return Vec::new(),
+        };
+
+        let path = home_file(SCORES_FILE);
+        let mut scores = match Scores::read(&path) {
+            Ok(scores) => scores,
+            Err(_) => return vec![" failed to read the leaderboard.".to_string()],
+        };
+
+        let board = self.board.name();
+        let date = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let rank = scores.insert(Score {
+            board: board.clone(),
+            time: stopped.duration_since(started).as_secs(),
+            name: player_name(),
+            date: scores::date(date),
+        });
+
+        let mut lines = vec![format!(" best times on {}:", board)];
+        if rank.is_some() && scores.write(&path).is_err() {
+            lines.push(" failed to save your time.".to_string());
+        }
+        for (i, score) in scores.board(&board).iter().enumerate() {
+            lines.push(format!(" {:2}. {:>6} {} {}{}",
+                               i + 1, format_time(score.time), score.date, score.name,
+                               if rank == Some(i) { " <- you" } else { "" }));
+        }
+
+        lines
+    }
+
+    /// End the game, stopping the clock and showing the given end screen.
+    ///
+    /// The given lines are shown below it.
+    fn end(&mut self, screen: &str, lines: &[String]) {
+        if self.stopped.is_none() {
+            self.stopped = Some(Instant::now());
+        }
+        self.print_status();
+
         //Goto top left corner
         write!(self.stdout, "{}", cursor::Goto(1, 1)).unwrap();
 
-        self.stdout.write(GAME_OVER.as_bytes()).unwrap();
+        self.stdout.write(screen.as_bytes()).unwrap();
+        // Show the seed, so the board can be played again.
+        write!(self.stdout, "\n\r seed {}", self.seed).unwrap();
+        for line in lines {
+            write!(self.stdout, "\n\r{}", line).unwrap();
+        }
         self.stdout.flush().unwrap();
 
         loop {
-            // Repeatedly read a single byte.
-            match self.stdin.next().unwrap().unwrap() {
+            // Repeatedly read a single key.
+            match self.next_key() {
                 Key::Char('r') => {
                     // Replay!
                     self.restart();
@@ -426,83 +767,39 @@ impl<R: Iterator<Item=Result<Key, std::io::Error>>, W: Write> Game<R, W> {
         self.reset();
         self.start();
     }
+}
 
-    /// Calculate the adjacent cells.
-    fn adjacent(&self, x: u16, y: u16) -> [(u16, u16); 8] {
-        let left = self.left(x);
-        let right = self.right(x);
-        let up = self.up(y);
-        let down = self.down(y);
-
-        [
-            // Left-up
-            (left, up),
-            // Up
-            (x, up),
-            // Right-up
-            (right, up),
-            // Left
-            (left, y),
-            // Right
-            (right, y),
-            // Left-down
-            (left, down),
-            // Down
-            (x, down),
-            // Right-down
-            (right, down)
-        ]
-    }
+/// Format a duration in seconds as minutes and seconds.
+fn format_time(secs: u64) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
 
-    /// Calculate the height (number of rows) of the grid.
-    fn height(&self) -> u16 {
-        (self.grid.len() / self.width as usize) as u16
-    }
+/// Get the path of a file in the home directory.
+fn home_file(name: &str) -> PathBuf {
+    env::var_os("HOME").map(PathBuf::from).unwrap_or_default().join(name)
+}
 
-    /// Calculate the y coordinate of the cell "above" a given y coordinate.
-    ///
-    /// This wraps when _y = 0_.
-    fn up(&self, y: u16) -> u16 {
-        if y == 0 {
-            // Upper bound reached. Wrap around.
-            self.height() - 1
-        } else {
-            y - 1
-        }
-    }
-    /// Calculate the y coordinate of the cell "below" a given y coordinate.
-    ///
-    /// This wraps when _y = h - 1_.
-    fn down(&self, y: u16) -> u16 {
-        if y + 1 == self.height() {
-            // Lower bound reached. Wrap around.
-            0
-        } else {
-            y + 1
-        }
-    }
-    /// Calculate the x coordinate of the cell "left to" a given x coordinate.
-    ///
-    /// This wraps when _x = 0_.
-    fn left(&self, x: u16) -> u16 {
-        if x == 0 {
-            // Lower bound reached. Wrap around.
-            self.width - 1
-        } else {
-            x - 1
-        }
-    }
-    /// Calculate the x coordinate of the cell "left to" a given x coordinate.
-    ///
-    /// This wraps when _x = w - 1_.
-    fn right(&self, x: u16) -> u16 {
-        if x + 1 == self.width {
-            // Upper bound reached. Wrap around.
-            0
-        } else {
-            x + 1
-        }
+/// Get a path to save a new game to, in the home directory, without overwriting a saved game.
+fn new_save_path() -> PathBuf {
+    let mut path = home_file(SAVE_FILE);
+    let mut n = 1;
+    while path.exists() {
+        n += 1;
+        path = home_file(&format!("{}-{}", SAVE_FILE, n));
     }
+
+    path
+}
+
+/// Get the name of the player, for the leaderboard.
+fn player_name() -> String {
+    env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or_else(|_| "anonymous".to_string())
+}
+
+/// Pick a new seed, based on the current time.
+fn new_seed() -> u64 {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    time.as_secs() ^ ((time.subsec_nanos() as u64) << 32)
 }
 
 fn main() {
@@ -510,12 +807,18 @@ fn main() {
     let mut width = None;
     let mut height = None;
     let mut diff = 6;
+    let mut no_guess = false;
+    let mut wrap = true;
+    let mut hex = false;
+    let mut load = None;
+    let mut seed = None;
+    let mut autoplay = false;
+    let mut mines = None;
+    let mut preset = None;
 
     // Get and lock the stdios.
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
-    let stdin = io::stdin();
-    let stdin = stdin.lock();
     let stderr = io::stderr();
     let mut stderr = stderr.lock();
 
@@ -570,6 +873,64 @@ fn main() {
             "-a" => diff = 4,
             "-i" => diff = 6,
             "-b" => diff = 10,
+            "--mines" => mines = Some(args.next().unwrap_or_else(|| {
+                stderr.write(b"no mine count given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            }).parse().unwrap_or_else(|_| {
+                stderr.write(b"invalid integer given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            })),
+            // The classic boards don't wrap, like --no-wrap.
+            "--beginner" => {
+                preset = Some(BEGINNER);
+                wrap = false;
+            },
+            "--intermediate" => {
+                preset = Some(INTERMEDIATE);
+                wrap = false;
+            },
+            "--expert" => {
+                preset = Some(EXPERT);
+                wrap = false;
+            },
+            "-n" | "--no-guess" => no_guess = true,
+            "--no-wrap" => wrap = false,
+            "--hex" => hex = true,
+            "--seed" => seed = Some(args.next().unwrap_or_else(|| {
+                stderr.write(b"no seed given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            }).parse().unwrap_or_else(|_| {
+                stderr.write(b"invalid integer given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            })),
+            "--autoplay" => autoplay = true,
+            "--scores" => {
+                // Print the leaderboard.
+                let scores = Scores::read(&home_file(SCORES_FILE)).unwrap_or_else(|err| {
+                    writeln!(stderr, "failed to read the leaderboard: {}", err).unwrap();
+                    stderr.flush().unwrap();
+                    process::exit(1);
+                });
+
+                for board in scores.boards() {
+                    writeln!(stdout, "{}:", board).unwrap();
+                    for (i, score) in scores.board(board).iter().enumerate() {
+                        writeln!(stdout, "  {:2}. {:>6} {} {}",
+                                 i + 1, format_time(score.time), score.date, score.name).unwrap();
+                    }
+                }
+                stdout.flush().unwrap();
+                process::exit(0);
+            },
+            "--load" => load = Some(PathBuf::from(args.next().unwrap_or_else(|| {
+                stderr.write(b"no save file given.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            }))),
             _ => {
                 stderr.write(b"Unknown argument.\n").unwrap();
                 stderr.flush().unwrap();
@@ -578,13 +939,89 @@ fn main() {
         }
     }
 
-    // We go to raw mode to make the control over the terminal more fine-grained.
-    let stdout = stdout.into_raw_mode().unwrap();
+    // Read the saved game before touching the terminal, so errors can be reported properly.
+    // The file is removed right away, so the game can't be resumed twice. If it is left
+    // unfinished again, it is saved back.
+    let save = load.as_ref().map(|path| {
+        let save = Save::read(path).unwrap_or_else(|err| {
+            writeln!(stderr, "{}: {}", path.display(), err).unwrap();
+            stderr.flush().unwrap();
+            process::exit(1);
+        });
+        if let Err(err) = fs::remove_file(path) {
+            writeln!(stderr, "{}: failed to remove the resumed game: {}", path.display(), err)
+                .unwrap();
+            stderr.flush().unwrap();
+            process::exit(1);
+        }
+
+        save
+    });
 
     let termsize = termion::terminal_size().ok();
-    let termwidth = termsize.map(|(w,_)| w - 2);
-    let termheight = termsize.map(|(_,h)| h - 2);
+    // Hexagonal cells take two columns each.
+    let termwidth = termsize.map(|(w,_)| if hex { (w - 2) / 2 } else { w - 2 });
+    // Leave room for the frame and the status line.
+    let termheight = termsize.map(|(_,h)| h - 3);
+    let options = match save {
+        // A resumed game keeps its settings, but may still be handed over to the autoplayer.
+        Some(ref save) => Options {
+            autoplay: autoplay,
+            .. save.options
+        },
+        None => {
+            let height = height.or(preset.map(|p| p.1));
+            if hex && wrap && height.map_or(false, |h| h % 2 == 1) {
+                // The shifted rows wouldn't line up across the edge.
+                stderr.write(b"wrapping hexagonal grids need an even height.\n").unwrap();
+                stderr.flush().unwrap();
+                process::exit(1);
+            }
+
+            Options {
+                width: width.or(preset.map(|p| p.0)).or(termwidth).unwrap_or(70),
+                height: height.or(termheight.map(|h| if hex && wrap { h & !1 } else { h }))
+                    .unwrap_or(40),
+                difficulty: diff,
+                mines: mines.or(preset.map(|p| p.2)),
+                wrap: wrap,
+                hex: hex,
+                no_guess: no_guess,
+                seed: seed,
+                autoplay: autoplay,
+            }
+        },
+    };
+
+    if let Some(mines) = options.mines {
+        // The first click and its neighbors are always free.
+        if mines as usize + 9 > options.width as usize * options.height as usize {
+            stderr.write(b"too many mines for the grid.\n").unwrap();
+            stderr.flush().unwrap();
+            process::exit(1);
+        }
+    }
+
+    // We go to raw mode to make the control over the terminal more fine-grained.
+    // Mouse support is enabled as well.
+    let stdout = MouseTerminal::from(stdout.into_raw_mode().unwrap());
+
+    // Read the keys asynchronously, so the clock can tick while waiting for input.
+    let stdin = async_stdin();
+
     // Initialize the game!
-    init(stdout, stdin, diff, width.or(termwidth).unwrap_or(70),
-                              height.or(termheight).unwrap_or(40));
+    if let Some(save) = init(stdout, stdin, options, save) {
+        // The game was left unfinished, so we save it for later, back to the file it came from.
+        let save_path = load.unwrap_or_else(new_save_path);
+        if let Err(err) = save.write(&save_path) {
+            writeln!(stderr, "failed to save the game to {}: {}", save_path.display(), err)
+                .unwrap();
+            stderr.flush().unwrap();
+            process::exit(1);
+        }
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        writeln!(stdout, "game saved. resume it with --load {}", save_path.display()).unwrap();
+    }
 }
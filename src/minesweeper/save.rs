@@ -0,0 +1,198 @@
+//! Saving and loading of unfinished games.
+//!
+//! Games are saved in a small text format. The first line holds the version of the format, then
+//! comes a line per setting, and finally the grid, one line per row. Every cell is written as a
+//! base 32 digit, holding the bits of the cell (see `MINE`, `REVEALED`, `OBSERVED`, `FLAGGED` and
+//! `UNSURE`).
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use board::{Cell, Mark};
+use Options;
+
+/// The first line of a save file, identifying the format and its version.
+const HEADER: &'static str = "minesweeper save 6";
+
+/// The bit set for cells holding a mine.
+const MINE: u8 = 1;
+/// The bit set for revealed cells.
+const REVEALED: u8 = 2;
+/// The bit set for observed cells.
+const OBSERVED: u8 = 4;
+/// The bit set for flagged cells.
+const FLAGGED: u8 = 8;
+/// The bit set for cells marked unsure.
+const UNSURE: u8 = 16;
+
+/// An unfinished game.
+pub struct Save {
+    /// The settings of the game.
+    pub options: Options,
+    /// The seed the board was generated from.
+    pub seed: u64,
+    /// The seed of the randomizer.
+    ///
+    /// The randomizer can't be serialized directly, so it is reseeded from this when saving.
+    pub rand: u64,
+    /// The x coordinate of the cursor.
+    pub x: u16,
+    /// The y coordinate of the cursor.
+    pub y: u16,
+    /// The points.
+    pub points: u16,
+    /// The time played so far, in seconds.
+    pub time: u64,
+    /// Did the autoplayer make moves in the game?
+    ///
+    /// If so, winning it isn't recorded as the player's score.
+    pub autoplayed: bool,
+    /// The grid.
+    pub grid: Box<[Cell]>,
+}
+
+/// Create an error for a malformed save file.
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid save file: {}", msg))
+}
+
+/// Parse the value of a setting.
+fn parse<T: FromStr>(name: &str, value: &str) -> io::Result<T> {
+    value.parse().map_err(|_| invalid(&format!("invalid value for {}", name)))
+}
+
+impl Save {
+    /// Write the game to a file.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+
+        writeln!(file, "{}", HEADER)?;
+        writeln!(file, "width {}", self.options.width)?;
+        writeln!(file, "height {}", self.options.height)?;
+        writeln!(file, "difficulty {}", self.options.difficulty)?;
+        match self.options.mines {
+            Some(mines) => writeln!(file, "mines {}", mines)?,
+            None => writeln!(file, "mines random")?,
+        }
+        writeln!(file, "wrap {}", self.options.wrap)?;
+        writeln!(file, "hex {}", self.options.hex)?;
+        writeln!(file, "no-guess {}", self.options.no_guess)?;
+        writeln!(file, "fixed-seed {}", self.options.seed.is_some())?;
+        writeln!(file, "seed {}", self.seed)?;
+        writeln!(file, "rand {}", self.rand)?;
+        writeln!(file, "cursor {} {}", self.x, self.y)?;
+        writeln!(file, "points {}", self.points)?;
+        writeln!(file, "time {}", self.time)?;
+        writeln!(file, "autoplayed {}", self.autoplayed)?;
+
+        for row in self.grid.chunks(self.options.width as usize) {
+            for cell in row {
+                let mark = match cell.mark {
+                    Mark::None => 0,
+                    Mark::Flag => FLAGGED,
+                    Mark::Unsure => UNSURE,
+                };
+                let bits = cell.mine as u8 * MINE
+                    | cell.revealed as u8 * REVEALED
+                    | cell.observed as u8 * OBSERVED
+                    | mark;
+                write!(file, "{}", char::from_digit(bits as u32, 32).unwrap())?;
+            }
+            writeln!(file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a game from a file.
+    pub fn read(path: &Path) -> io::Result<Save> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+
+        if lines.next().unwrap_or_else(|| Ok(String::new()))? != HEADER {
+            return Err(invalid("unknown format or version"));
+        }
+
+        // Read a setting, in the form "name values...", returning the given number of values.
+        let mut setting = |name: &str, count: usize| -> io::Result<Vec<String>> {
+            let line = lines.next().unwrap_or_else(|| Err(invalid("unexpected end of file")))?;
+            let words: Vec<String> = line.split_whitespace().map(|w| w.to_string()).collect();
+
+            if words.len() != count + 1 || words[0] != name {
+                return Err(invalid(&format!("expected {} followed by {} value(s)", name, count)));
+            }
+            Ok(words[1..].to_vec())
+        };
+
+        let width: u16 = parse("width", &setting("width", 1)?[0])?;
+        let height: u16 = parse("height", &setting("height", 1)?[0])?;
+        let difficulty = parse("difficulty", &setting("difficulty", 1)?[0])?;
+        let mines = match setting("mines", 1)?[0].as_str() {
+            "random" => None,
+            mines => Some(parse("mines", mines)?),
+        };
+        let wrap = parse("wrap", &setting("wrap", 1)?[0])?;
+        let hex = parse("hex", &setting("hex", 1)?[0])?;
+        let no_guess = parse("no-guess", &setting("no-guess", 1)?[0])?;
+        let fixed_seed = parse("fixed-seed", &setting("fixed-seed", 1)?[0])?;
+        let seed = parse("seed", &setting("seed", 1)?[0])?;
+        let rand = parse("rand", &setting("rand", 1)?[0])?;
+        let cursor = setting("cursor", 2)?;
+        let x = parse("cursor", &cursor[0])?;
+        let y = parse("cursor", &cursor[1])?;
+        let points = parse("points", &setting("points", 1)?[0])?;
+        let time = parse("time", &setting("time", 1)?[0])?;
+        let autoplayed = parse("autoplayed", &setting("autoplayed", 1)?[0])?;
+
+        if width == 0 || height == 0 || x >= width || y >= height || difficulty == 0
+            || hex && wrap && height % 2 == 1 {
+            return Err(invalid("settings out of range"));
+        }
+
+        let mut grid = Vec::with_capacity(width as usize * height as usize);
+        for _ in 0..height {
+            let line = lines.next().unwrap_or_else(|| Err(invalid("too few rows")))?;
+            if line.len() != width as usize {
+                return Err(invalid("row of wrong width"));
+            }
+
+            for c in line.chars() {
+                let bits = c.to_digit(32).ok_or_else(|| invalid("invalid cell"))? as u8;
+                grid.push(Cell {
+                    mine: bits & MINE != 0,
+                    revealed: bits & REVEALED != 0,
+                    observed: bits & OBSERVED != 0,
+                    mark: match bits & (FLAGGED | UNSURE) {
+                        0 => Mark::None,
+                        FLAGGED => Mark::Flag,
+                        UNSURE => Mark::Unsure,
+                        _ => return Err(invalid("cell both flagged and unsure")),
+                    },
+                });
+            }
+        }
+
+        Ok(Save {
+            options: Options {
+                width: width,
+                height: height,
+                difficulty: difficulty,
+                mines: mines,
+                wrap: wrap,
+                hex: hex,
+                no_guess: no_guess,
+                seed: if fixed_seed { Some(seed) } else { None },
+                autoplay: false,
+            },
+            seed: seed,
+            rand: rand,
+            x: x,
+            y: y,
+            points: points,
+            time: time,
+            autoplayed: autoplayed,
+            grid: grid.into_boxed_slice(),
+        })
+    }
+}
@@ -0,0 +1,145 @@
+//! The leaderboard of best completion times.
+//!
+//! The scores are kept in a text file, one per line, with the board, the time in seconds, the
+//! name of the player and the date separated by tabs.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+/// The number of scores kept per board.
+pub const KEPT: usize = 10;
+
+/// A completed game.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Score {
+    /// The board played, describing its size and mines (e.g. "9x9 10 mines").
+    pub board: String,
+    /// The completion time, in seconds.
+    pub time: u64,
+    /// The name of the player.
+    pub name: String,
+    /// The date of the game (YYYY-MM-DD).
+    pub date: String,
+}
+
+/// The leaderboard.
+pub struct Scores {
+    /// The scores, grouped by board and sorted by time within each board.
+    scores: Vec<Score>,
+}
+
+/// Remove the characters which would break the format.
+fn clean(s: &str) -> String {
+    s.replace(|c| c == '\t' || c == '\n' || c == '\r', " ")
+}
+
+/// Format a UNIX timestamp as a date (YYYY-MM-DD).
+pub fn date(secs: u64) -> String {
+    // This is the civil-from-days algorithm by Howard Hinnant, using eras of 400 years starting
+    // at March 1st.
+    let days = secs / 86400 + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+                       - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as u64;
+
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+impl Scores {
+    /// Read the leaderboard from a file.
+    ///
+    /// A missing file gives an empty leaderboard. Malformed lines are skipped.
+    pub fn read(path: &Path) -> io::Result<Scores> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Scores { scores: Vec::new() });
+            },
+            Err(err) => return Err(err),
+        };
+
+        let mut scores = Scores { scores: Vec::new() };
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 4 {
+                continue;
+            }
+
+            if let Ok(time) = fields[1].parse() {
+                scores.insert(Score {
+                    board: fields[0].to_string(),
+                    time: time,
+                    name: fields[2].to_string(),
+                    date: fields[3].to_string(),
+                });
+            }
+        }
+
+        Ok(scores)
+    }
+
+    /// Write the leaderboard to a file.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+
+        for score in self.scores.iter() {
+            writeln!(file, "{}\t{}\t{}\t{}",
+                     clean(&score.board), score.time, clean(&score.name), clean(&score.date))?;
+        }
+
+        Ok(())
+    }
+
+    /// Insert a score.
+    ///
+    /// Returns its rank on the board (starting at 0), or `None` if it didn't make it to the
+    /// leaderboard. Ties are ranked by age, so older scores stay ahead.
+    pub fn insert(&mut self, score: Score) -> Option<usize> {
+        let rank = self.board(&score.board).iter().take_while(|s| s.time <= score.time).count();
+        if rank >= KEPT {
+            return None;
+        }
+
+        // Insert it after the better scores of its board, or at the end if it is a new board.
+        let pos = match self.scores.iter().position(|s| s.board == score.board) {
+            Some(first) => first + rank,
+            None => self.scores.len(),
+        };
+        self.scores.insert(pos, score);
+
+        // Drop the score pushed out of the leaderboard, if any.
+        if let Some(last) = self.scores.iter()
+            .enumerate()
+            .filter(|&(_, s)| s.board == self.scores[pos].board)
+            .map(|(i, _)| i)
+            .nth(KEPT) {
+            self.scores.remove(last);
+        }
+
+        Some(rank)
+    }
+
+    /// Get the best scores of a board, best first.
+    pub fn board(&self, board: &str) -> Vec<&Score> {
+        self.scores.iter().filter(|s| s.board == board).collect()
+    }
+
+    /// Get the names of the boards with scores.
+    pub fn boards(&self) -> Vec<&str> {
+        let mut res: Vec<&str> = Vec::new();
+        for score in self.scores.iter() {
+            if !res.contains(&score.board.as_str()) {
+                res.push(&score.board);
+            }
+        }
+        res
+    }
+}
@@ -0,0 +1,179 @@
+//! A small constraint solver for minesweeper boards.
+//!
+//! The solver only knows about cells by their index and the list of cells adjacent to each of
+//! them, so it doesn't care about the shape of the board or whether it wraps.
+
+/// What is known about a cell.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Knowledge {
+    /// Nothing is known about the cell.
+    Unknown,
+    /// The cell is known to hold a mine.
+    Mine,
+    /// The cell is revealed, showing the number of adjacent mines.
+    Revealed(u8),
+}
+
+/// A conclusion drawn from the revealed numbers.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Deduction {
+    /// The cell is safe to reveal.
+    Safe(usize),
+    /// The cell holds a mine.
+    Mine(usize),
+}
+
+/// A constraint given by a revealed number.
+///
+/// Exactly `mines` of the unknown `cells` hold a mine.
+struct Constraint {
+    /// The unknown cells around the number, sorted.
+    cells: Vec<usize>,
+    /// The number of mines left among them.
+    mines: usize,
+}
+
+/// Collect the constraints of every revealed number bordering an unknown cell.
+fn constraints(knowledge: &[Knowledge], adjacency: &[Vec<usize>]) -> Vec<Constraint> {
+    let mut res = Vec::new();
+
+    for (i, k) in knowledge.iter().enumerate() {
+        if let Knowledge::Revealed(n) = *k {
+            let mut cells = Vec::new();
+            let mut known = 0;
+            for &c in adjacency[i].iter() {
+                match knowledge[c] {
+                    Knowledge::Unknown => cells.push(c),
+                    Knowledge::Mine => known += 1,
+                    Knowledge::Revealed(_) => {},
+                }
+            }
+
+            // Numbers that are already satisfied tell us nothing new. Neither do numbers which
+            // contradict what we know, which can happen when the player's flags are wrong.
+            if !cells.is_empty() && known <= n as usize {
+                cells.sort();
+                res.push(Constraint {
+                    cells: cells,
+                    mines: n as usize - known,
+                });
+            }
+        }
+    }
+
+    res
+}
+
+/// Conclude the state of `cells`, if `mines` of them holding a mine leaves no choice.
+fn conclude(seen: &mut [bool], res: &mut Vec<Deduction>, cells: &[usize], mines: usize) {
+    if mines != 0 && mines != cells.len() {
+        return;
+    }
+
+    for &c in cells {
+        if !seen[c] {
+            seen[c] = true;
+            res.push(if mines == 0 {
+                Deduction::Safe(c)
+            } else {
+                Deduction::Mine(c)
+            });
+        }
+    }
+}
+
+/// Find the cells whose state follows from the revealed numbers.
+///
+/// First, every number is considered on its own: if its mines are all accounted for, the rest of
+/// its neighbors are safe, and if it has exactly as many unknown neighbors as missing mines, they
+/// are all mines. If that gives nothing, pairs of numbers are compared: when the unknown
+/// neighbors of one number are a subset of the neighbors of another, the difference holds the
+/// difference in mines.
+///
+/// An empty result means that the player will have to guess.
+pub fn deduce(knowledge: &[Knowledge], adjacency: &[Vec<usize>]) -> Vec<Deduction> {
+    let constraints = constraints(knowledge, adjacency);
+    // Cells already concluded, to avoid duplicates in the result.
+    let mut seen = vec![false; knowledge.len()];
+    let mut res = Vec::new();
+
+    for c in constraints.iter() {
+        conclude(&mut seen, &mut res, &c.cells, c.mines);
+    }
+
+    if !res.is_empty() {
+        return res;
+    }
+
+    // Index the constraints by the cells they cover, so we only compare overlapping ones.
+    let mut covering = vec![Vec::new(); knowledge.len()];
+    for (n, c) in constraints.iter().enumerate() {
+        for &i in c.cells.iter() {
+            covering[i].push(n);
+        }
+    }
+
+    for a in constraints.iter() {
+        for &b in covering[a.cells[0]].iter() {
+            let b = &constraints[b];
+            if b.cells.len() <= a.cells.len() || b.mines < a.mines {
+                continue;
+            }
+
+            if a.cells.iter().all(|c| b.cells.binary_search(c).is_ok()) {
+                let diff: Vec<usize> = b.cells.iter()
+                    .cloned()
+                    .filter(|c| a.cells.binary_search(c).is_err())
+                    .collect();
+                conclude(&mut seen, &mut res, &diff, b.mines - a.mines);
+            }
+        }
+    }
+
+    res
+}
+
+/// Reveal a cell the way the game does, spreading over free cells.
+pub fn reveal(knowledge: &mut [Knowledge], mines: &[bool], adjacency: &[Vec<usize>], cell: usize) {
+    let mut stack = vec![cell];
+
+    while let Some(i) = stack.pop() {
+        if let Knowledge::Revealed(_) = knowledge[i] {
+            continue;
+        }
+
+        let v = adjacency[i].iter().filter(|&&c| mines[c]).count() as u8;
+        knowledge[i] = Knowledge::Revealed(v);
+
+        if v == 0 {
+            for &c in adjacency[i].iter() {
+                if knowledge[c] == Knowledge::Unknown {
+                    stack.push(c);
+                }
+            }
+        }
+    }
+}
+
+/// Play a board by pure deduction, starting by revealing `start`.
+///
+/// The returned knowledge is what the player ends up knowing. The board can be solved without
+/// guessing if and only if every cell without a mine is revealed in it.
+pub fn play(mines: &[bool], adjacency: &[Vec<usize>], start: usize) -> Vec<Knowledge> {
+    let mut knowledge = vec![Knowledge::Unknown; mines.len()];
+    reveal(&mut knowledge, mines, adjacency, start);
+
+    loop {
+        let deductions = deduce(&knowledge, adjacency);
+        if deductions.is_empty() {
+            return knowledge;
+        }
+
+        for d in deductions {
+            match d {
+                Deduction::Safe(c) => reveal(&mut knowledge, mines, adjacency, c),
+                Deduction::Mine(c) => knowledge[c] = Knowledge::Mine,
+            }
+        }
+    }
+}
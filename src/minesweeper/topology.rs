@@ -0,0 +1,260 @@
+//! The shapes of the grid.
+//!
+//! Cells are always stored row by row, and addressed by (x, y). A topology tells which cells are
+//! adjacent, how the cursor moves between them, and where they are drawn.
+
+/// A direction the cursor can move in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Direction {
+    /// Up.
+    Up,
+    /// Down.
+    Down,
+    /// Left.
+    Left,
+    /// Right.
+    Right,
+}
+
+/// The shape of a grid.
+pub trait Topology {
+    /// Calculate the adjacent cells.
+    fn adjacent(&self, x: u16, y: u16) -> Vec<(u16, u16)>;
+
+    /// Calculate the cell the cursor reaches when moving from (x, y) in a given direction.
+    fn step(&self, x: u16, y: u16, dir: Direction) -> (u16, u16);
+
+    /// Get the position (column, row) of the cell on the screen, relative to the top-left cell.
+    fn screen_pos(&self, x: u16, y: u16) -> (u16, u16);
+
+    /// Get the cell drawn at the given position on the screen, if any.
+    ///
+    /// This is the inverse of `screen_pos`.
+    fn cell_at(&self, column: u16, row: u16) -> Option<(u16, u16)>;
+
+    /// Get the number of columns taken by the grid on the screen.
+    fn screen_width(&self) -> u16;
+}
+
+/// Create the topology of a grid.
+pub fn new(width: u16, height: u16, wrap: bool, hex: bool) -> Box<dyn Topology> {
+    let square = Square {
+        width: width,
+        height: height,
+        wrap: wrap,
+    };
+
+    if hex {
+        Box::new(Hex { square: square })
+    } else {
+        Box::new(square)
+    }
+}
+
+/// A grid of square cells, each having eight neighbors.
+pub struct Square {
+    /// Width of the grid.
+    width: u16,
+    /// Height of the grid.
+    height: u16,
+    /// Does the grid wrap around its edges?
+    ///
+    /// If not, cells on the edges have fewer neighbors, like in classic minesweeper.
+    wrap: bool,
+}
+
+impl Square {
+    /// Calculate the y coordinate of the cell "above" a given y coordinate.
+    ///
+    /// This wraps when _y = 0_, or stays put if the grid doesn't wrap.
+    fn up(&self, y: u16) -> u16 {
+        if y == 0 && !self.wrap {
+            // Upper bound reached. Stop here.
+            y
+        } else if y == 0 {
+            // Upper bound reached. Wrap around.
+            self.height - 1
+        } else {
+            y - 1
+        }
+    }
+    /// Calculate the y coordinate of the cell "below" a given y coordinate.
+    ///
+    /// This wraps when _y = h - 1_, or stays put if the grid doesn't wrap.
+    fn down(&self, y: u16) -> u16 {
+        if y + 1 == self.height && !self.wrap {
+            // Lower bound reached. Stop here.
+            y
+        } else if y + 1 == self.height {
+            // Lower bound reached. Wrap around.
+            0
+        } else {
+            y + 1
+        }
+    }
+    /// Calculate the x coordinate of the cell "left to" a given x coordinate.
+    ///
+    /// This wraps when _x = 0_, or stays put if the grid doesn't wrap.
+    fn left(&self, x: u16) -> u16 {
+        if x == 0 && !self.wrap {
+            // Lower bound reached. Stop here.
+            x
+        } else if x == 0 {
+            // Lower bound reached. Wrap around.
+            self.width - 1
+        } else {
+            x - 1
+        }
+    }
+    /// Calculate the x coordinate of the cell "left to" a given x coordinate.
+    ///
+    /// This wraps when _x = w - 1_, or stays put if the grid doesn't wrap.
+    fn right(&self, x: u16) -> u16 {
+        if x + 1 == self.width && !self.wrap {
+            // Upper bound reached. Stop here.
+            x
+        } else if x + 1 == self.width {
+            // Upper bound reached. Wrap around.
+            0
+        } else {
+            x + 1
+        }
+    }
+
+    /// Calculate the cell at an offset from (x, y).
+    ///
+    /// Returns `None` if it is outside a grid which doesn't wrap.
+    fn offset(&self, x: u16, y: u16, dx: i32, dy: i32) -> Option<(u16, u16)> {
+        let (w, h) = (self.width as i32, self.height as i32);
+        let (x, y) = (x as i32 + dx, y as i32 + dy);
+
+        if self.wrap {
+            Some((((x % w + w) % w) as u16, ((y % h + h) % h) as u16))
+        } else if x >= 0 && y >= 0 && x < w && y < h {
+            Some((x as u16, y as u16))
+        } else {
+            None
+        }
+    }
+}
+
+impl Topology for Square {
+    /// Calculate the adjacent cells.
+    ///
+    /// If the grid doesn't wrap, cells on the edges have five neighbors, and corners only three.
+    fn adjacent(&self, x: u16, y: u16) -> Vec<(u16, u16)> {
+        if !self.wrap {
+            let mut res = Vec::with_capacity(8);
+            for ny in y.saturating_sub(1)..::std::cmp::min(y + 2, self.height) {
+                for nx in x.saturating_sub(1)..::std::cmp::min(x + 2, self.width) {
+                    if (nx, ny) != (x, y) {
+                        res.push((nx, ny));
+                    }
+                }
+            }
+            return res;
+        }
+
+        let left = self.left(x);
+        let right = self.right(x);
+        let up = self.up(y);
+        let down = self.down(y);
+
+        vec![
+            // Left-up
+            (left, up),
+            // Up
+            (x, up),
+            // Right-up
+            (right, up),
+            // Left
+            (left, y),
+            // Right
+            (right, y),
+            // Left-down
+            (left, down),
+            // Down
+            (x, down),
+            // Right-down
+            (right, down)
+        ]
+    }
+
+    fn step(&self, x: u16, y: u16, dir: Direction) -> (u16, u16) {
+        match dir {
+            Direction::Up => (x, self.up(y)),
+            Direction::Down => (x, self.down(y)),
+            Direction::Left => (self.left(x), y),
+            Direction::Right => (self.right(x), y),
+        }
+    }
+
+    fn screen_pos(&self, x: u16, y: u16) -> (u16, u16) {
+        (x, y)
+    }
+
+    fn cell_at(&self, column: u16, row: u16) -> Option<(u16, u16)> {
+        if column < self.width && row < self.height {
+            Some((column, row))
+        } else {
+            None
+        }
+    }
+
+    fn screen_width(&self) -> u16 {
+        self.width
+    }
+}
+
+/// A grid of hexagonal cells, each having six neighbors.
+///
+/// The cells are laid out in rows like the square grid, but every odd row is shifted half a cell
+/// to the right. To make room for that, every cell takes two columns on the screen.
+///
+/// When wrapping, the height must be even, so the shifting matches across the top and bottom.
+pub struct Hex {
+    /// The square grid the cells are stored and moved around in.
+    square: Square,
+}
+
+/// The offsets of the neighbors of a hexagonal cell on an even row.
+const HEX_EVEN_ROW: [(i32, i32); 6] = [(-1, -1), (0, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+/// The offsets of the neighbors of a hexagonal cell on an odd row.
+const HEX_ODD_ROW: [(i32, i32); 6] = [(0, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+
+impl Topology for Hex {
+    /// Calculate the adjacent cells.
+    ///
+    /// These are the two cells to the left and right, and the two cells touching the cell on each
+    /// of the rows above and below.
+    fn adjacent(&self, x: u16, y: u16) -> Vec<(u16, u16)> {
+        let offsets = if y % 2 == 0 { &HEX_EVEN_ROW } else { &HEX_ODD_ROW };
+
+        offsets.iter()
+            .filter_map(|&(dx, dy)| self.square.offset(x, y, dx, dy))
+            .collect()
+    }
+
+    fn step(&self, x: u16, y: u16, dir: Direction) -> (u16, u16) {
+        // Moving up or down zigzags, thanks to the shifted rows.
+        self.square.step(x, y, dir)
+    }
+
+    fn screen_pos(&self, x: u16, y: u16) -> (u16, u16) {
+        (2 * x + y % 2, y)
+    }
+
+    fn cell_at(&self, column: u16, row: u16) -> Option<(u16, u16)> {
+        let shift = row % 2;
+        if column < shift {
+            return None;
+        }
+
+        // The column right of a cell belongs to it as well.
+        self.square.cell_at((column - shift) / 2, row)
+    }
+
+    fn screen_width(&self) -> u16 {
+        2 * self.square.width
+    }
+}